@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use alloy::primitives::U256;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::Value;
+use crate::core::{OAuthVerification, PresentationCheck, Verification};
+use crate::helpers::{load_oauth_signers, FamilySources, OAUTH_SOURCE, TLSN_SOURCE};
+use crate::{config, custom_handlers};
+use crate::services::{Handlers, VERIFICATIONS_PATH};
+
+const OAUTH_CONFIGS: [(&str, &str); 2] = [
+    ("oauth_verifications.json", "oauth_signers.json"),
+    ("oauth_verifications_staging.json", "oauth_signers_staging.json"),
+];
+
+// Top-level members of a JSON object in file order. Unlike a map
+// it keeps duplicate keys, which serde would otherwise silently drop.
+struct Entries(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object keyed by credential group id")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[derive(Default)]
+struct Report {
+    problems: Vec<String>,
+}
+
+impl Report {
+    fn add(&mut self, file: &str, problem: impl fmt::Display) {
+        self.problems.push(format!("{file}: {problem}"));
+    }
+
+    // Group ids end up as the uint256 `credentialGroupId` of the attestation
+    fn entries(&mut self, file: &str) -> Vec<(String, Value)> {
+        let entries = match std::fs::read_to_string(file) {
+            Ok(data) => serde_json::from_str::<Entries>(&data),
+            Err(e) => {
+                self.add(file, format!("failed to read: {e}"));
+                return Vec::new();
+            },
+        };
+        let entries = match entries {
+            Ok(Entries(entries)) => entries,
+            Err(e) => {
+                self.add(file, format!("failed to parse: {e}"));
+                return Vec::new();
+            },
+        };
+        let mut seen = HashSet::new();
+        for (id, _) in &entries {
+            if !seen.insert(id.as_str()) {
+                self.add(file, format!("group {id}: duplicate group id"));
+            }
+            if U256::from_str(id).is_err() {
+                self.add(file, format!("group {id}: group id is not numeric"));
+            }
+        }
+        entries
+    }
+
+    fn presentation_check(
+        &mut self,
+        file: &str,
+        location: &str,
+        check: &PresentationCheck,
+        handlers: &Handlers,
+    ) {
+        if let Some(name) = &check.custom_handler {
+            match handlers.get(name) {
+                Some(handler) => if let Err(e) = handler.validate_params(&check.handler_params) {
+                    self.add(file, format!("{location}: {name}: {e}"));
+                },
+                None => self.add(file, format!("{location}: unknown custom_handler {name}")),
+            }
+        }
+        for problem in check.problems() {
+            self.add(file, format!("{location}: {problem}"));
+        }
+    }
+
+    // Returns the families of the TLSN groups, which each OAuth config
+    // is checked against
+    fn verifications(&mut self, handlers: &Handlers) -> FamilySources {
+        let file = VERIFICATIONS_PATH;
+        let mut verifications = Vec::new();
+        for (id, value) in self.entries(file) {
+            match serde_json::from_value::<Verification>(value) {
+                Ok(verification) => verifications.push((id, verification)),
+                Err(e) => self.add(file, format!("group {id}: {e}")),
+            }
+        }
+        let mut families = FamilySources::default();
+        for (id, verification) in &verifications {
+            if verification.id != *id {
+                self.add(file, format!("group {id}: id field is {}", verification.id));
+            }
+            if let Err(e) = families.add(id, verification.family, TLSN_SOURCE, &verification.host) {
+                self.add(file, e);
+            }
+            if let Some(response) = &verification.response {
+                for problem in response.problems() {
+                    self.add(file, format!("group {id}: {problem}"));
+                }
+            }
+            self.presentation_check(file, &format!("group {id} user_id"), &verification.user_id, handlers);
+            for (index, expr) in verification.checks.iter().enumerate() {
+                for check in expr.leaves() {
+                    self.presentation_check(file, &format!("group {id} checks[{index}]"), check, handlers);
+                }
+            }
+        }
+        families
+    }
+
+    fn oauth_verifications(&mut self, file: &str, signers_file: &str, mut families: FamilySources) {
+        let mut groups = Vec::new();
+        let mut verifications = Vec::new();
+        for (id, value) in self.entries(file) {
+            match serde_json::from_value::<OAuthVerification>(value) {
+                Ok(verification) => verifications.push((id.clone(), verification)),
+                Err(e) => self.add(file, format!("group {id}: {e}")),
+            }
+            groups.push(id);
+        }
+        for (id, verification) in &verifications {
+            if verification.id != *id {
+                self.add(file, format!("group {id}: id field is {}", verification.id));
+            }
+            if let Err(e) = families.add(id, verification.family, OAUTH_SOURCE, &verification.domain) {
+                self.add(file, e);
+            }
+        }
+
+        // Duplicate and non-numeric ids of the signers file
+        self.entries(signers_file);
+        let signers = match load_oauth_signers(signers_file) {
+            Ok(signers) => signers,
+            Err(e) => {
+                self.add(signers_file, e);
+                return;
+            },
+        };
+        for id in groups {
+            if signers.get(&id).is_none_or(|signers| signers.is_empty()) {
+                self.add(file, format!("group {id}: no signer in {signers_file}"));
+            }
+        }
+    }
+}
+
+// `verifier check-config`: lints every config file against the
+// registered handlers and prints all problems instead of the first one
+pub fn run() -> Result<(), Box<dyn Error>> {
+    // WASM_HANDLERS_FILE may only be set in .env
+    config::load_env();
+    let handlers = custom_handlers::load_all()?;
+
+    let mut report = Report::default();
+    let families = report.verifications(&handlers);
+    for (file, signers_file) in OAUTH_CONFIGS {
+        report.oauth_verifications(file, signers_file, families.clone());
+    }
+
+    if report.problems.is_empty() {
+        println!("config OK");
+        return Ok(());
+    }
+    for problem in &report.problems {
+        println!("{problem}");
+    }
+    Err(format!("{} config problem(s) found", report.problems.len()).into())
+}
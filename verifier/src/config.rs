@@ -3,54 +3,265 @@ use std::sync::OnceLock;
 use std::env;
 use alloy::hex;
 use alloy::signers::k256::SecretKey;
+use chrono::{DateTime, Utc};
 use dotenv::from_filename;
+use serde::Deserialize;
+
+#[derive(Clone, Debug)]
+pub struct TrustedNotary {
+    pub id: String,
+    pub key: VerifyingKey,
+    pub not_before: Option<DateTime<Utc>>,
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+impl TrustedNotary {
+    fn covers(&self, time: DateTime<Utc>) -> bool {
+        self.not_before.is_none_or(|not_before| time >= not_before)
+            && self.not_after.is_none_or(|not_after| time <= not_after)
+    }
+
+    // The connection time is signed by the notary itself, so it alone
+    // can't retire a key: a leaked key could backdate its sessions.
+    // The window must cover the verifier's clock as well.
+    pub fn is_valid_at(&self, connection_time: u64, now: DateTime<Utc>) -> bool {
+        let Some(connection_time) = DateTime::<Utc>::from_timestamp(connection_time as i64, 0) else {
+            return false;
+        };
+        self.covers(now) && self.covers(connection_time)
+    }
+}
+
+#[derive(Deserialize)]
+struct NotaryEntry {
+    id: String,
+    alg: String,
+    key: String,
+    #[serde(default)]
+    not_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    not_after: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialIdScheme {
+    // keccak256(user_id || app_id || secret), what credential ids
+    // registered before versioning were derived with. It has no source
+    // tag; it stays active until the cutover to a tagged version and is
+    // then only kept to look those registrations up.
+    Keccak,
+    HmacSha256,
+}
+
+// Secrets are never rotated in place: a new secret gets a new version and
+// the old one is kept for as long as its credential ids must be recoverable
+#[derive(Clone)]
+pub struct CredentialIdSecret {
+    pub version: u32,
+    pub scheme: CredentialIdScheme,
+    pub secret: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct CredentialIdSecretEntry {
+    version: u32,
+    #[serde(default = "default_credential_id_scheme")]
+    scheme: CredentialIdScheme,
+    secret: String,
+}
+
+fn default_credential_id_scheme() -> CredentialIdScheme {
+    CredentialIdScheme::HmacSha256
+}
+
+#[derive(Clone)]
+pub struct CredentialIdSecrets {
+    pub secrets: Vec<CredentialIdSecret>,
+    // Version attestations are signed with, always a tagged scheme
+    pub active: u32,
+    // Responses also list the credential id under every configured version
+    pub migration: bool,
+}
 
 #[derive(Clone)]
 pub struct Config {
-    pub notary_key: VerifyingKey,
-    pub private_key: SecretKey,
+    pub notaries: Vec<TrustedNotary>,
+    // Kept apart from the signing key so that the two
+    // can be stored and rotated independently
+    pub credential_id_secrets: CredentialIdSecrets,
     pub port: String,
 }
 
+impl Config {
+    // Notary that signed the attestation, if it is trusted now
+    // and was at the time the TLS session took place
+    pub fn trusted_notary(&self, key: &VerifyingKey, time: u64) -> Option<&TrustedNotary> {
+        let now = Utc::now();
+        self.notaries
+            .iter()
+            .find(|notary| &notary.key == key && notary.is_valid_at(time, now))
+    }
+}
+
 static CONFIG: OnceLock<Config> = OnceLock::new();
 
 pub fn init() {
     get();
 }
 
-pub fn get() -> &'static Config {
-    from_filename(".env").ok();
-    from_filename(".env.dev").ok();
-    
-    let alg: KeyAlgId =
-        match env::var("NOTARY_KEY_ALG")
-            .expect("NOTARY_KEY_ALG is not set")
-            .as_str()
-        {
-            "K256" => KeyAlgId::K256,
-            "P256" => KeyAlgId::P256,
-            _ => panic!("Unsupported NOTARY_KEY_ALG"),
-        };
+fn key_alg(alg: &str) -> KeyAlgId {
+    match alg {
+        "K256" => KeyAlgId::K256,
+        "P256" => KeyAlgId::P256,
+        _ => panic!("Unsupported notary key algorithm {alg}"),
+    }
+}
+
+// Notaries from NOTARY_KEYS_FILE, plus the legacy single
+// NOTARY_KEY_ALG/NOTARY_KEY_HEX pair registered as "default"
+pub fn notaries() -> Vec<TrustedNotary> {
+    let mut notaries = Vec::new();
+
+    if let Ok(filename) = env::var("NOTARY_KEYS_FILE") {
+        let data = std::fs::read_to_string(&filename)
+            .unwrap_or_else(|_| panic!("failed to read {filename}"));
+        let entries: Vec<NotaryEntry> = serde_json::from_str(&data)
+            .unwrap_or_else(|e| panic!("failed to parse {filename}: {e}"));
+        for entry in entries {
+            let data = hex::decode(&entry.key)
+                .unwrap_or_else(|_| panic!("Invalid hex format in notary key {}", entry.id));
+            notaries.push(TrustedNotary {
+                key: VerifyingKey {
+                    alg: key_alg(&entry.alg),
+                    data,
+                },
+                id: entry.id,
+                not_before: entry.not_before,
+                not_after: entry.not_after,
+            });
+        }
+    }
 
-    let data: Vec<u8> = hex::decode(
-        &env::var("NOTARY_KEY_HEX").expect("NOTARY_KEY_HEX is not set")
-    ).expect("Invalid hex format in NOTARY_KEY_HEX");
-    
+    if let Ok(key) = env::var("NOTARY_KEY_HEX") {
+        let alg = key_alg(
+            &env::var("NOTARY_KEY_ALG").expect("NOTARY_KEY_ALG is not set")
+        );
+        let data: Vec<u8> = hex::decode(&key)
+            .expect("Invalid hex format in NOTARY_KEY_HEX");
+        notaries.push(TrustedNotary {
+            id: "default".to_string(),
+            key: VerifyingKey {
+                alg,
+                data,
+            },
+            not_before: None,
+            not_after: None,
+        });
+    }
+
+    if notaries.is_empty() {
+        panic!("neither NOTARY_KEYS_FILE nor NOTARY_KEY_HEX is set");
+    }
+    notaries
+}
+
+pub fn private_key() -> SecretKey {
     let private_key = env::var("PRIVATE_KEY_HEX").expect("PRIVATE_KEY_HEX is not set");
-    let private_key = SecretKey::from_slice(
+    SecretKey::from_slice(
         hex::decode(private_key)
             .expect("Invalid hex format in PRIVATE_KEY_HEX")
             .as_slice()
-    ).expect("Invalid private key");
-    
-    let port = env::var("PORT").expect("PORT is not set");
-    
-    CONFIG.get_or_init(|| Config {
-        port,
-        notary_key: VerifyingKey {
-            alg,
-            data,
-        },
-        private_key,
+    ).expect("Invalid private key")
+}
+
+// Secrets from CREDENTIAL_ID_SECRETS_FILE or, without it, version 1 keyed
+// by CREDENTIAL_ID_SECRET_HEX, plus version 0 with the legacy derivation
+// keyed by the signing key when it is set, for lookups
+fn credential_id_secrets() -> CredentialIdSecrets {
+    let mut secrets = Vec::new();
+
+    if let Ok(filename) = env::var("CREDENTIAL_ID_SECRETS_FILE") {
+        let data = std::fs::read_to_string(&filename)
+            .unwrap_or_else(|_| panic!("failed to read {filename}"));
+        let entries: Vec<CredentialIdSecretEntry> = serde_json::from_str(&data)
+            .unwrap_or_else(|e| panic!("failed to parse {filename}: {e}"));
+        for entry in entries {
+            let secret = hex::decode(&entry.secret)
+                .unwrap_or_else(|_| panic!("Invalid hex format in credential id secret {}", entry.version));
+            if entry.scheme == CredentialIdScheme::HmacSha256 && secret.len() < 32 {
+                panic!("credential id secret {} must be at least 32 bytes", entry.version);
+            }
+            if secrets.iter().any(|known: &CredentialIdSecret| known.version == entry.version) {
+                panic!("duplicate credential id secret version {}", entry.version);
+            }
+            secrets.push(CredentialIdSecret {
+                version: entry.version,
+                scheme: entry.scheme,
+                secret,
+            });
+        }
+    } else {
+        let secret = env::var("CREDENTIAL_ID_SECRET_HEX")
+            .expect("neither CREDENTIAL_ID_SECRETS_FILE nor CREDENTIAL_ID_SECRET_HEX is set");
+        let secret = hex::decode(secret).expect("Invalid hex format in CREDENTIAL_ID_SECRET_HEX");
+        if secret.len() < 32 {
+            panic!("CREDENTIAL_ID_SECRET_HEX must be at least 32 bytes");
+        }
+        if env::var("PRIVATE_KEY_HEX").is_ok() {
+            secrets.push(CredentialIdSecret {
+                version: 0,
+                scheme: CredentialIdScheme::Keccak,
+                secret: private_key().to_bytes().to_vec(),
+            });
+        }
+        secrets.push(CredentialIdSecret {
+            version: 1,
+            scheme: CredentialIdScheme::HmacSha256,
+            secret,
+        });
+    }
+
+    if secrets.iter().any(|secret| secret.secret.is_empty()) {
+        panic!("credential id secret is empty");
+    }
+    // Moving to a newer version changes every user's credential id, so it
+    // only happens once CREDENTIAL_ID_VERSION is set to it, after the
+    // registry and clients have migrated
+    let active = match env::var("CREDENTIAL_ID_VERSION") {
+        Ok(version) => version.parse::<u32>().expect("Invalid CREDENTIAL_ID_VERSION"),
+        Err(_) => secrets
+            .iter()
+            .map(|secret| secret.version)
+            .min()
+            .expect("no credential id secret configured"),
+    };
+    if !secrets.iter().any(|secret| secret.version == active) {
+        panic!("credential id secret version {active} is not configured");
+    }
+
+    CredentialIdSecrets {
+        secrets,
+        active,
+        migration: env::var("CREDENTIAL_ID_MIGRATION").is_ok_and(|v| v == "true"),
+    }
+}
+
+pub fn load_env() {
+    from_filename(".env").ok();
+    from_filename(".env.dev").ok();
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| {
+        load_env();
+
+        let port = env::var("PORT").expect("PORT is not set");
+
+        Config {
+            port,
+            notaries: notaries(),
+            credential_id_secrets: credential_id_secrets(),
+        }
     })
 }
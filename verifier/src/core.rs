@@ -1,5 +1,8 @@
 pub mod verification;
 mod oauth_verification;
+mod transcript;
+mod http;
 
 pub use verification::*;
-pub use oauth_verification::OAuthVerification;
\ No newline at end of file
+pub use oauth_verification::{OAuthVerification, OAuthTimestampError};
+pub use transcript::Transcript;
\ No newline at end of file
@@ -0,0 +1,213 @@
+use std::io::Read;
+use std::ops::Range;
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+const CRLF: &[u8] = b"\r\n";
+const HEADERS_END: &[u8] = b"\r\n\r\n";
+// Decompressed bodies larger than this are treated as not decodable,
+// a few MB of gzip can otherwise expand to gigabytes
+const MAX_DECODED_BODY: u64 = 16 * 1024 * 1024;
+
+// HTTP message rebuilt from the authed parts of a transcript. Redacted
+// header lines are left out, and the body is only present when all of it
+// was revealed.
+#[derive(Debug, Clone)]
+pub struct HttpMessage {
+    pub start_line: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub body_start: usize,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpMessage {
+    pub fn parse(data: &[u8], authed: &[Range<usize>]) -> Option<Self> {
+        let covered = |range: Range<usize>| {
+            authed.iter().any(|authed| authed.start <= range.start && range.end <= authed.end)
+        };
+
+        // The header/body boundary only counts if it was actually revealed
+        let headers_end = find(data, HEADERS_END)
+            .filter(|pos| covered(*pos..pos + HEADERS_END.len()))?;
+        let body_start = headers_end + HEADERS_END.len();
+
+        let mut lines = Vec::new();
+        let mut line_start = 0;
+        while line_start < headers_end {
+            let line_end = find(&data[line_start..headers_end], CRLF)
+                .map_or(headers_end, |pos| line_start + pos);
+            lines.push(line_start..line_end);
+            line_start = line_end + CRLF.len();
+        }
+
+        let mut lines = lines.into_iter();
+        let start_line = lines
+            .next()
+            .filter(|line| covered(line.start..line.end + CRLF.len()))
+            .and_then(|line| String::from_utf8(data[line].to_vec()).ok());
+
+        let headers: Vec<(String, String)> = lines
+            .filter(|line| covered(line.clone()))
+            .filter_map(|line| {
+                let line = std::str::from_utf8(&data[line]).ok()?;
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            })
+            .collect();
+
+        let mut message = Self { start_line, headers, body_start, body: None };
+        if covered(body_start..data.len()) {
+            message.body = message.decode_body(&data[body_start..]);
+        }
+        Some(message)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Status code of a response, e.g. 200 for `HTTP/1.1 200 OK`
+    pub fn status(&self) -> Option<u16> {
+        self.start_line
+            .as_ref()?
+            .split(' ')
+            .nth(1)?
+            .parse()
+            .ok()
+    }
+
+    pub fn body_text(&self) -> Option<&str> {
+        std::str::from_utf8(self.body.as_ref()?).ok()
+    }
+
+    fn decode_body(&self, raw: &[u8]) -> Option<Vec<u8>> {
+        let body = match self.header("transfer-encoding") {
+            Some(encoding) if encoding.eq_ignore_ascii_case("chunked") => dechunk(raw)?,
+            Some(_) => return None,
+            None => raw.to_vec(),
+        };
+        let mut decoded = Vec::new();
+        match self.header("content-encoding") {
+            None => return Some(body),
+            Some(encoding) if encoding.eq_ignore_ascii_case("identity") => return Some(body),
+            Some(encoding) if encoding.eq_ignore_ascii_case("gzip") => {
+                GzDecoder::new(body.as_slice())
+                    .take(MAX_DECODED_BODY + 1)
+                    .read_to_end(&mut decoded)
+                    .ok()?
+            },
+            Some(encoding) if encoding.eq_ignore_ascii_case("deflate") => {
+                ZlibDecoder::new(body.as_slice())
+                    .take(MAX_DECODED_BODY + 1)
+                    .read_to_end(&mut decoded)
+                    .ok()?
+            },
+            Some(_) => return None,
+        };
+        if decoded.len() as u64 > MAX_DECODED_BODY {
+            return None;
+        }
+        Some(decoded)
+    }
+}
+
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).position(|window| window == needle)
+}
+
+fn dechunk(mut raw: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = find(raw, CRLF)?;
+        let size = std::str::from_utf8(&raw[..line_end]).ok()?;
+        // Chunk extensions (`;name=value`) are ignored
+        let size = size.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        raw = &raw[line_end + CRLF.len()..];
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(raw.get(..size)?);
+        raw = raw.get(size..)?.strip_prefix(CRLF)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use super::*;
+
+    fn full(data: &[u8]) -> [Range<usize>; 1] {
+        let all = 0..data.len();
+        [all]
+    }
+
+    #[test]
+    fn parses_a_fully_revealed_response() {
+        let data = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"id\":1}";
+        let message = HttpMessage::parse(data, &full(data)).unwrap();
+        assert_eq!(message.status(), Some(200));
+        assert_eq!(message.header("content-type"), Some("application/json"));
+        assert_eq!(message.body_text(), Some("{\"id\":1}"));
+    }
+
+    #[test]
+    fn dechunks_the_body() {
+        let data = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4;ext=1\r\n{\"id\r\n4\r\n\":1}\r\n0\r\n\r\n";
+        let message = HttpMessage::parse(data, &full(data)).unwrap();
+        assert_eq!(message.body_text(), Some("{\"id\":1}"));
+    }
+
+    #[test]
+    fn rejects_a_truncated_chunk() {
+        let data = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n10\r\n{\"id\":1}\r\n0\r\n\r\n";
+        let message = HttpMessage::parse(data, &full(data)).unwrap();
+        assert_eq!(message.body, None);
+    }
+
+    #[test]
+    fn gunzips_the_body() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"{\"id\":1}").unwrap();
+        let mut data = b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\n\r\n".to_vec();
+        data.extend(encoder.finish().unwrap());
+        let message = HttpMessage::parse(&data, &full(&data)).unwrap();
+        assert_eq!(message.body_text(), Some("{\"id\":1}"));
+    }
+
+    #[test]
+    fn refuses_to_inflate_past_the_limit() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![b'a'; MAX_DECODED_BODY as usize + 1]).unwrap();
+        let mut data = b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\n\r\n".to_vec();
+        data.extend(encoder.finish().unwrap());
+        let message = HttpMessage::parse(&data, &full(&data)).unwrap();
+        assert_eq!(message.body, None);
+    }
+
+    #[test]
+    fn leaves_out_what_was_not_revealed() {
+        let data = b"HTTP/1.1 200 OK\r\nSet-Cookie: secret\r\nContent-Type: text/plain\r\n\r\nhello";
+        let cookie = find(data, b"Set-Cookie").unwrap();
+        let content_type = find(data, b"Content-Type").unwrap();
+        // The cookie line and the last byte of the body are redacted
+        let authed = [0..cookie, content_type..data.len() - 1];
+        let message = HttpMessage::parse(data, &authed).unwrap();
+        assert_eq!(message.status(), Some(200));
+        assert_eq!(message.header("set-cookie"), None);
+        assert_eq!(message.header("content-type"), Some("text/plain"));
+        assert_eq!(message.body, None);
+    }
+
+    #[test]
+    fn needs_the_header_boundary() {
+        let data = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nhello";
+        let boundary = find(data, HEADERS_END).unwrap();
+        let authed = [0..boundary + 1, boundary + 2..data.len()];
+        assert!(HttpMessage::parse(data, &authed).is_none());
+    }
+}
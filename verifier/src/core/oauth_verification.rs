@@ -1,17 +1,118 @@
 use std::error::Error;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::time::{SystemTime, UNIX_EPOCH};
+use alloy::primitives::U256;
+use alloy::sol_types::Eip712Domain;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use tracing::{instrument, error, warn};
 use crate::services::HandlersManager;
+use crate::core::{CheckFailure, FailureReason, VerificationFailed};
+
+// Applied to groups that don't set them, so that
+// every message timestamp is checked
+const DEFAULT_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_MAX_FUTURE_SKEW_SECS: u64 = 5 * 60;
+
+// How the OAuth service signs `OauthMessage` for this group: `personal_sign`
+// over the ABI-encoded message, or EIP-712 typed data under its own domain
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "scheme", rename_all = "snake_case")]
+pub enum SignatureScheme {
+    #[default]
+    PersonalSign,
+    Eip712 {
+        name: String,
+        version: String,
+        chain_id: u64,
+    },
+}
+
+impl SignatureScheme {
+    pub fn eip712_domain(&self) -> Option<Eip712Domain> {
+        match self {
+            SignatureScheme::PersonalSign => None,
+            SignatureScheme::Eip712 { name, version, chain_id } => Some(Eip712Domain::new(
+                Some(name.clone().into()),
+                Some(version.clone().into()),
+                Some(U256::from(*chain_id)),
+                None,
+                None,
+            )),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuthVerification {
     pub(crate) id: String,
     pub(crate) domain: String,
+    // On-chain credential family, 0 for a standalone group (see `credential_tag`)
+    #[serde(default)]
+    pub(crate) family: u32,
     pub(crate) score: i32,
+    // Maximum age of the signed OAuth message in seconds, a day if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_age: Option<u64>,
+    // How far in the future the message timestamp may be, in seconds,
+    // five minutes if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_future_skew: Option<u64>,
+    #[serde(default)]
+    pub(crate) signature_scheme: SignatureScheme,
+    // Accept the legacy message layout that is not bound to the
+    // credential group, app id and commitment until this date, while
+    // the group's signer moves to the bound one; never if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) allow_legacy_message_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub enum OAuthTimestampError {
+    Expired { age: u64, max_age: u64 },
+    FutureDated { skew: u64, max_skew: u64 },
+}
+
+impl fmt::Display for OAuthTimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OAuthTimestampError::Expired { age, max_age } => {
+                write!(f, "OAuth message expired: {age}s old, max age is {max_age}s")
+            },
+            OAuthTimestampError::FutureDated { skew, max_skew } => {
+                write!(f, "OAuth message is {skew}s in the future, max skew is {max_skew}s")
+            },
+        }
+    }
 }
 
+impl Error for OAuthTimestampError {}
+
 impl OAuthVerification {
+    pub fn accepts_legacy_message(&self, now: DateTime<Utc>) -> bool {
+        self.allow_legacy_message_until.is_some_and(|until| now < until)
+    }
+
+    pub fn check_timestamp(&self, timestamp: u64) -> Result<(), OAuthTimestampError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_secs();
+
+        let max_skew = self.max_future_skew.unwrap_or(DEFAULT_MAX_FUTURE_SKEW_SECS);
+        if timestamp > now.saturating_add(max_skew) {
+            warn!("OAuth message is future-dated");
+            return Err(OAuthTimestampError::FutureDated { skew: timestamp - now, max_skew });
+        }
+        let max_age = self.max_age.unwrap_or(DEFAULT_MAX_AGE_SECS);
+        if now.saturating_sub(timestamp) > max_age {
+            warn!("OAuth message expired");
+            return Err(OAuthTimestampError::Expired { age: now - timestamp, max_age });
+        }
+        Ok(())
+    }
+
     #[instrument(
         level="info",
         name="verification_check",
@@ -21,14 +122,23 @@ impl OAuthVerification {
         domain: String,
         score: i32
     ) -> Result<(), Box<dyn Error>> {
+        // Observed values are only logged, the client gets what was expected
         if score < self.score {
-            error!("not enough score");
-            return Err("low score".into());
+            error!("not enough score: {score}");
+            return Err(VerificationFailed::from(CheckFailure {
+                expected: Some(json!({ "type": "gte", "value": self.score })),
+                message: Some("low score".to_string()),
+                ..CheckFailure::new("score", FailureReason::NotSatisfied)
+            }).into());
         }
 
         if domain != self.domain {
-            error!("wrong domain");
-            return Err("wrong domain".into());
+            error!("wrong domain: {domain}");
+            return Err(VerificationFailed::from(CheckFailure {
+                expected: Some(self.domain.clone().into()),
+                message: Some("wrong domain".to_string()),
+                ..CheckFailure::new("domain", FailureReason::NotSatisfied)
+            }).into());
         }
 
         Ok(())
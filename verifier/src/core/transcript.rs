@@ -0,0 +1,78 @@
+use std::ops::Range;
+use tracing::debug;
+use super::http::HttpMessage;
+
+#[derive(Debug, Clone)]
+pub struct AuthedRange {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+// Authenticated part of the sent or received data. Windows only see the
+// ranges that are valid UTF-8, in the same order the presentation reveals
+// them, while the HTTP message is parsed from all authed bytes.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    ranges: Vec<AuthedRange>,
+    http: Option<HttpMessage>,
+}
+
+impl Transcript {
+    pub fn new(data: &[u8], ranges: impl Iterator<Item = Range<usize>>) -> Self {
+        let authed: Vec<Range<usize>> = ranges
+            .filter(|range| range.end <= data.len())
+            .collect();
+        let ranges = authed
+            .iter()
+            .filter_map(|range| match std::str::from_utf8(&data[range.clone()]) {
+                Ok(text) => Some(AuthedRange { range: range.clone(), text: text.to_string() }),
+                Err(_) => {
+                    debug!("authed range {range:?} is not valid UTF-8");
+                    None
+                },
+            })
+            .collect();
+        let http = HttpMessage::parse(data, &authed);
+
+        Self { ranges, http }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.ranges.get(index).map(|authed| authed.text.as_str())
+    }
+
+    pub fn windows(&self) -> impl Iterator<Item = &str> {
+        self.ranges.iter().map(|authed| authed.text.as_str())
+    }
+
+    pub fn http(&self) -> Option<&HttpMessage> {
+        self.http.as_ref()
+    }
+
+    pub fn body_start(&self) -> Option<usize> {
+        self.http.as_ref().map(|http| http.body_start)
+    }
+
+    // Fully revealed body after removing chunked and gzip/deflate encoding
+    pub fn body(&self) -> Option<&str> {
+        self.http.as_ref()?.body_text()
+    }
+
+    // Revealed text starting at `offset` of the received data,
+    // up to the end of the authed range that contains it
+    pub fn text_at(&self, offset: usize) -> Option<&str> {
+        let authed = self.ranges.iter().find(|authed| authed.range.contains(&offset))?;
+        authed.text.get(offset - authed.range.start..)
+    }
+
+    pub fn find_anchor(&self, anchor: &str) -> Option<&str> {
+        self.ranges
+            .iter()
+            .map(|authed| authed.text.trim_start())
+            .find(|text| text.starts_with(anchor))
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.http.as_ref()?.header(name)
+    }
+}
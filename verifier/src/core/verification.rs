@@ -1,66 +1,134 @@
 mod check;
 mod window;
+mod json_path;
 mod presentation_check;
+mod check_expr;
+mod aggregate;
+mod request_check;
+mod response_check;
+mod failure;
+mod context;
 
 use std::error::Error;
-use std::fmt::Debug;
-use alloy::primitives::U256;
+use std::fmt::{self, Debug};
+use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use tracing::{instrument, error, warn};
 pub use presentation_check::PresentationCheck;
-use crate::services::HandlersManager;
+pub use check_expr::CheckExpr;
+pub use request_check::RequestCheck;
+pub use response_check::ResponseCheck;
+pub use failure::{CheckFailure, FailureReason, VerificationFailed};
+pub use context::CheckContext;
+
+// Applied to groups that don't set `max_age`, so that an
+// old presentation can't be replayed at any age
+const DEFAULT_MAX_AGE_SECS: u64 = 24 * 60 * 60;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Verification {
     pub(crate) id: String,
     pub(crate) host: String,
+    // On-chain credential family, 0 for a standalone group (see `credential_tag`)
+    #[serde(default)]
+    pub(crate) family: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) request: Option<RequestCheck>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) response: Option<ResponseCheck>,
+    // Maximum age of the TLS session in seconds, a day if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_age: Option<u64>,
+    // Ids of the notaries this group accepts, any trusted notary if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) notaries: Vec<String>,
     pub(crate) user_id: PresentationCheck,
-    pub(crate) checks: Vec<PresentationCheck>
+    pub(crate) checks: Vec<CheckExpr>
+}
+
+#[derive(Debug)]
+pub struct PresentationExpired {
+    pub age: u64,
+    pub max_age: u64,
 }
 
+impl fmt::Display for PresentationExpired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "presentation expired: TLS session is {}s old, max age is {}s", self.age, self.max_age)
+    }
+}
+
+impl Error for PresentationExpired {}
+
 impl Verification {
+    pub fn accepts_notary(&self, notary_id: &str) -> bool {
+        self.notaries.is_empty() || self.notaries.iter().any(|id| id == notary_id)
+    }
+
+    pub fn check_freshness(&self, connection_time: u64) -> Result<(), PresentationExpired> {
+        let max_age = self.max_age.unwrap_or(DEFAULT_MAX_AGE_SECS);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_secs();
+        let age = now.saturating_sub(connection_time);
+        if age > max_age {
+            warn!("presentation expired");
+            return Err(PresentationExpired { age, max_age });
+        }
+        Ok(())
+    }
+
     #[instrument(
         level="info",
         name="verification_check",
-        skip(self, server_name, transcript),
+        skip(self, ctx),
     )]
-    pub async fn check(
-        &self,
-        server_name: String,
-        transcript: &Vec<String>,
-        app_id: &U256,
-    ) -> Result<(), Box<dyn Error>> {
-        if server_name != self.host {
-            error!("wrong server name");
-            return Err("wrong server name".into());
+    pub async fn check(&self, ctx: &CheckContext<'_>) -> Result<(), Box<dyn Error>> {
+        if ctx.server_name != self.host {
+            error!("wrong server name: {}", ctx.server_name);
+            return Err(VerificationFailed::from(CheckFailure {
+                expected: Some(self.host.clone().into()),
+                ..CheckFailure::new("host", FailureReason::WrongHost)
+            }).into());
         }
 
-        let Some(user_id_data) = transcript.get(self.user_id.window.id) else {
+        if let Some(request) = &self.request
+            && let Err(e) = request.check(ctx.sent)
+        {
+            let failure = CheckFailure::new("request", FailureReason::RequestMismatch).with_message(e);
+            return Err(VerificationFailed::from(failure).into());
+        }
+        if let Some(response) = &self.response
+            && let Err(e) = response.check(ctx.received)
+        {
+            let failure = CheckFailure::new("response", FailureReason::ResponseMismatch).with_message(e);
+            return Err(VerificationFailed::from(failure).into());
+        }
+
+        let Some(user_id_data) = self.user_id.window.locate(ctx.received) else {
             error!("missing user_id");
-            return Err("missing user_id".into());
+            let failure = self.user_id.failure("user_id", FailureReason::MissingWindow);
+            return Err(VerificationFailed::from(failure).into());
         };
         if !self.user_id.check(user_id_data) {
             error!("wrong user_id");
-            return Err("wrong user_id".into());
+            let failure = CheckFailure {
+                observed: Some(self.user_id.observed_hint(user_id_data)),
+                ..self.user_id.failure("user_id", FailureReason::NotSatisfied)
+            };
+            return Err(VerificationFailed::from(failure).into());
         }
 
-        for check in &self.checks {
-            let Some(data) = transcript.get(check.window.id) else {
-                error!("missing check window data");
-                return Err("missing check window data".into());
-            };
-            if check.custom_handler.is_some() {
-                let (success, _) = HandlersManager::execute(check, data, app_id)
-                    .await
-                    .inspect_err(
-                        |err| warn!("custom handler error: {err}")
-                    )?;
-                if !success {
-                    warn!("custom handler failed check");
-                    return Err("check failed".into());
+        for (index, check) in self.checks.iter().enumerate() {
+            let path = format!("checks[{index}]");
+            let mut failures = Vec::new();
+            if !matches!(check.evaluate(ctx, path.clone(), &mut failures).await, Ok(true)) {
+                warn!("check failed");
+                if failures.is_empty() {
+                    failures.push(CheckFailure::new(path, FailureReason::NotSatisfied));
                 }
-            } else if !check.check(data) {
-                return Err("check failed".into());
+                return Err(VerificationFailed { failures }.into());
             }
         }
 
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use super::check::{Check, CheckableValue};
+use super::json_path::JsonPath;
+
+// Reduces a selected JSON array to a number that the check's
+// threshold is then applied to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Aggregate {
+    Count,
+    CountWhere {
+        #[serde(rename = "where")]
+        predicate: ElementPredicate,
+    },
+    Sum { path: JsonPath },
+    Distinct { path: JsonPath },
+}
+
+// A path that is absent, or a value the check does not apply to (e.g.
+// `contains` on a number), never matches, so `not` matches it. Pair `not`
+// with `{"path": "description", "type": "any"}` inside `all`, which matches
+// any value present at the path (`null` included).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum ElementPredicate {
+    All { all: Vec<ElementPredicate> },
+    Any { any: Vec<ElementPredicate> },
+    Not { not: Box<ElementPredicate> },
+    Match(ElementMatch),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementMatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<JsonPath>,
+    #[serde(flatten)]
+    pub check: Check,
+}
+
+impl ElementPredicate {
+    pub fn matches(&self, item: &Value) -> bool {
+        match self {
+            ElementPredicate::All { all } => all.iter().all(|predicate| predicate.matches(item)),
+            ElementPredicate::Any { any } => any.iter().any(|predicate| predicate.matches(item)),
+            ElementPredicate::Not { not } => !not.matches(item),
+            ElementPredicate::Match(ElementMatch { path: Some(path), check }) => path
+                .select(item)
+                .is_some_and(|value| (&value).check_against(check)),
+            ElementPredicate::Match(ElementMatch { path: None, check }) => item.check_against(check),
+        }
+    }
+}
+
+impl Aggregate {
+    // Counts say nothing about the content of the items
+    pub fn is_count(&self) -> bool {
+        matches!(self, Aggregate::Count | Aggregate::CountWhere { .. } | Aggregate::Distinct { .. })
+    }
+
+    pub fn evaluate(&self, items: &[Value]) -> Option<Decimal> {
+        match self {
+            Aggregate::Count => Some(Decimal::from(items.len())),
+            Aggregate::CountWhere { predicate } => Some(Decimal::from(
+                items.iter().filter(|item| predicate.matches(item)).count()
+            )),
+            Aggregate::Sum { path } => items
+                .iter()
+                .filter_map(|item| path.select(item))
+                .try_fold(Decimal::ZERO, |sum, value| {
+                    let value = match value {
+                        Value::Number(number) => Decimal::from_str(&number.to_string()).ok()?,
+                        Value::String(string) => Decimal::from_str(&string).ok()?,
+                        _ => return None,
+                    };
+                    sum.checked_add(value)
+                }),
+            Aggregate::Distinct { path } => Some(Decimal::from(
+                items
+                    .iter()
+                    .filter_map(|item| path.select(item))
+                    .map(|value| value.to_string())
+                    .collect::<HashSet<_>>()
+                    .len()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn evaluate(aggregate: Value, items: Value) -> Option<Decimal> {
+        let aggregate: Aggregate = serde_json::from_value(aggregate).unwrap();
+        aggregate.evaluate(items.as_array().unwrap())
+    }
+
+    #[test]
+    fn counts_items_matching_a_predicate() {
+        let rides = json!([
+            {"uuid": "a", "description": "Completed"},
+            {"uuid": "b", "description": "Canceled by rider"},
+            {"uuid": "c"},
+            {"description": "Completed"},
+            {"uuid": "e", "description": "Completed"},
+        ]);
+        let completed = json!({"type": "count_where", "where": {"all": [
+            {"path": "uuid", "type": "any"},
+            {"path": "description", "type": "any"},
+            {"not": {"path": "description", "type": "contains", "value": "Canceled"}},
+        ]}});
+        assert_eq!(evaluate(completed, rides.clone()), Some(Decimal::from(2)));
+
+        // Without the presence checks `not` also matches the items lacking a description
+        let not_canceled = json!({"type": "count_where", "where":
+            {"not": {"path": "description", "type": "contains", "value": "Canceled"}}
+        });
+        assert_eq!(evaluate(not_canceled, rides.clone()), Some(Decimal::from(4)));
+
+        let any = json!({"type": "count_where", "where": {"any": [
+            {"path": "uuid", "type": "str_eq", "value": "c"},
+            {"path": "description", "type": "str_eq", "value": "Canceled by rider"},
+        ]}});
+        assert_eq!(evaluate(any, rides.clone()), Some(Decimal::from(2)));
+        assert_eq!(evaluate(json!({"type": "count"}), rides), Some(Decimal::from(5)));
+    }
+
+    #[test]
+    fn matches_items_without_a_path() {
+        let long = json!({"type": "count_where", "where": {"type": "len_gte", "value": 3}});
+        assert_eq!(evaluate(long, json!(["ab", "abc", 1234, "abcd"])), Some(Decimal::from(2)));
+    }
+
+    #[test]
+    fn counts_distinct_values() {
+        let items = json!([{"id": 1}, {"id": "1"}, {"id": 1}, {"id": {"a": 1}}, {}]);
+        assert_eq!(evaluate(json!({"type": "distinct", "path": "id"}), items), Some(Decimal::from(3)));
+    }
+
+    #[test]
+    fn sums_numbers_and_numeric_strings() {
+        let items = json!([{"fare": 10.5}, {"fare": "4.25"}, {"fare": 1}, {}]);
+        assert_eq!(evaluate(json!({"type": "sum", "path": "fare"}), items), Some(Decimal::new(1575, 2)));
+    }
+
+    #[test]
+    fn sum_fails_on_other_values_and_on_overflow() {
+        let items = json!([{"fare": 10}, {"fare": "ten"}]);
+        assert_eq!(evaluate(json!({"type": "sum", "path": "fare"}), items), None);
+        let items = json!([{"fare": 10}, {"fare": true}]);
+        assert_eq!(evaluate(json!({"type": "sum", "path": "fare"}), items), None);
+
+        let max = Decimal::MAX.to_string();
+        let items = json!([{"fare": max}, {"fare": max}]);
+        assert_eq!(evaluate(json!({"type": "sum", "path": "fare"}), items), None);
+    }
+}
@@ -1,4 +1,9 @@
-use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use chrono::{DateTime, NaiveDate};
+use regex::Regex;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -6,14 +11,157 @@ use serde_json::{Value};
 #[serde(rename_all = "snake_case")]
 pub enum Check {
     LenGte(usize),
+    LenLte(usize),
     Gte(i64),
     Lte(i64),
     Eq(i64),
+    Between(Decimal, Decimal),
+    GteFloat(f64),
+    LteFloat(f64),
+    GteDecimal(Decimal),
+    LteDecimal(Decimal),
+    EqDecimal(Decimal),
     Contains(String),
+    StrEq(String),
+    Regex(Pattern),
+    OneOf(Vec<Value>),
+    BeforeDate(Timestamp),
+    AfterDate(Timestamp),
     Custom,
     Any,
 }
 
+// What a check is applied to, as far as it is known from the config alone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueKind {
+    // Aggregates always produce a number
+    Number,
+    // Response headers and windows without a key or path
+    Text,
+}
+
+impl Check {
+    pub fn applies_to(&self, kind: ValueKind) -> bool {
+        match (self, kind) {
+            (Check::Any | Check::Custom, _) => true,
+            (Check::OneOf(values), ValueKind::Number) => values.iter().any(Value::is_number),
+            (Check::OneOf(values), ValueKind::Text) => values.iter().any(Value::is_string),
+            (check, ValueKind::Number) => check.is_numeric(),
+            (_, ValueKind::Text) => true,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.get("type")?.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    // Config mistakes that would make the check never (or always) pass
+    pub fn problems(&self, kind: Option<ValueKind>) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(kind) = kind
+            && !self.applies_to(kind)
+        {
+            problems.push(format!("`{}` check cannot apply to {kind:?} values", self.name()));
+        }
+        match self {
+            Check::Between(min, max) if min > max => {
+                problems.push(format!("`between` range is empty: {min} > {max}"));
+            },
+            Check::OneOf(values) if values.is_empty() => {
+                problems.push("`one_of` has no values".to_string());
+            },
+            _ => {},
+        }
+        problems
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Check::Gte(_) | Check::Lte(_) | Check::Eq(_) | Check::Between(..) |
+            Check::GteFloat(_) | Check::LteFloat(_) |
+            Check::GteDecimal(_) | Check::LteDecimal(_) | Check::EqDecimal(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn is_match(&self, value: &str) -> bool {
+        self.0.is_match(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    // Matches the whole value rather than any part of it
+    pub fn anchored(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(&format!("^(?:{pattern})$")).map(Pattern)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(Pattern)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+// Unix time in seconds, configured either as a number or an ISO-8601 string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    // Numbers this large are milliseconds: as seconds they would be past year 5000
+    const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+    pub fn from_unix(value: i64) -> Self {
+        if value.abs() >= Self::MILLIS_THRESHOLD {
+            Timestamp(value / 1000)
+        } else {
+            Timestamp(value)
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+            return Some(Timestamp(date.timestamp()));
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Some(Timestamp(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp()));
+        }
+        value.parse::<i64>().ok().map(Self::from_unix)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Number(number) => number
+                .as_i64()
+                .map(Timestamp::from_unix)
+                .ok_or_else(|| serde::de::Error::custom("timestamp must be an integer")),
+            Value::String(date) => Timestamp::parse(&date)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid date {date}"))),
+            _ => Err(serde::de::Error::custom("expected an ISO-8601 date or a unix timestamp")),
+        }
+    }
+}
+
 pub trait CheckableValue {
     fn check_against(&self, check: &Check) -> bool;
 }
@@ -24,6 +172,52 @@ impl CheckableValue for i64 {
             Check::Gte(threshold) => *self >= *threshold,
             Check::Lte(threshold) => *self <= *threshold,
             Check::Eq(threshold) => *self == *threshold,
+            Check::GteFloat(_) | Check::LteFloat(_) => (*self as f64).check_against(check),
+            Check::Between(..) | Check::GteDecimal(_) | Check::LteDecimal(_) | Check::EqDecimal(_) => {
+                Decimal::from(*self).check_against(check)
+            },
+            Check::OneOf(values) => values.iter().any(|value| value.as_i64() == Some(*self)),
+            Check::BeforeDate(date) => Timestamp::from_unix(*self) < *date,
+            Check::AfterDate(date) => Timestamp::from_unix(*self) > *date,
+            Check::Any | Check::Custom => true,
+            _ => false,
+        }
+    }
+}
+
+impl CheckableValue for f64 {
+    fn check_against(&self, check: &Check) -> bool {
+        match check {
+            Check::GteFloat(threshold) => *self >= *threshold,
+            Check::LteFloat(threshold) => *self <= *threshold,
+            Check::Gte(threshold) => *self >= *threshold as f64,
+            Check::Lte(threshold) => *self <= *threshold as f64,
+            Check::Between(..) | Check::GteDecimal(_) | Check::LteDecimal(_) | Check::EqDecimal(_) => {
+                Decimal::try_from(*self).is_ok_and(|value| value.check_against(check))
+            },
+            Check::OneOf(values) => values.iter().any(|value| value.as_f64() == Some(*self)),
+            Check::Any | Check::Custom => true,
+            _ => false,
+        }
+    }
+}
+
+impl CheckableValue for Decimal {
+    fn check_against(&self, check: &Check) -> bool {
+        match check {
+            Check::GteDecimal(threshold) => self >= threshold,
+            Check::LteDecimal(threshold) => self <= threshold,
+            Check::EqDecimal(threshold) => self == threshold,
+            Check::Between(min, max) => self >= min && self <= max,
+            Check::Gte(threshold) => *self >= Decimal::from(*threshold),
+            Check::Lte(threshold) => *self <= Decimal::from(*threshold),
+            Check::Eq(threshold) => *self == Decimal::from(*threshold),
+            Check::GteFloat(_) | Check::LteFloat(_) => {
+                self.to_f64().is_some_and(|value| value.check_against(check))
+            },
+            Check::OneOf(values) => values
+                .iter()
+                .any(|value| Decimal::from_str(&value.to_string()).is_ok_and(|value| value == *self)),
             Check::Any | Check::Custom => true,
             _ => false,
         }
@@ -37,6 +231,16 @@ impl CheckableValue for &str {
             Check::Contains(value) => {
                 self.contains(value)
             },
+            Check::StrEq(value) => *self == value,
+            Check::Regex(pattern) => pattern.is_match(self),
+            Check::OneOf(values) => values.iter().any(|value| value.as_str() == Some(*self)),
+            Check::LenGte(value) => self.chars().count() >= *value,
+            Check::LenLte(value) => self.chars().count() <= *value,
+            Check::BeforeDate(date) => Timestamp::parse(self).is_some_and(|value| value < *date),
+            Check::AfterDate(date) => Timestamp::parse(self).is_some_and(|value| value > *date),
+            check if check.is_numeric() => {
+                Decimal::from_str(self).is_ok_and(|value| value.check_against(check))
+            },
             _ => false,
         }
     }
@@ -46,8 +250,114 @@ impl CheckableValue for &Vec<Value> {
     fn check_against(&self, check: &Check) -> bool {
         match check {
             Check::LenGte(value) => self.len() >= *value,
+            Check::LenLte(value) => self.len() <= *value,
             Check::Any | Check::Custom => true,
             _ => false,
         }
     }
-}
\ No newline at end of file
+}
+
+impl CheckableValue for &Value {
+    fn check_against(&self, check: &Check) -> bool {
+        match self {
+            Value::Number(number) => {
+                if let Some(number) = number.as_i64() {
+                    number.check_against(check)
+                } else if let Ok(number) = Decimal::from_str(&number.to_string()) {
+                    number.check_against(check)
+                } else {
+                    number.as_f64().is_some_and(|number| number.check_against(check))
+                }
+            },
+            Value::String(string) => string.as_str().check_against(check),
+            Value::Array(array) => array.check_against(check),
+            Value::Bool(_) | Value::Null => match check {
+                Check::OneOf(values) => values.contains(self),
+                Check::Any | Check::Custom => true,
+                _ => false,
+            },
+            Value::Object(_) => matches!(check, Check::Any | Check::Custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn passes(check: Value, value: Value) -> bool {
+        let check: Check = serde_json::from_value(check).unwrap();
+        (&value).check_against(&check)
+    }
+
+    #[test]
+    fn one_of_matches_values_of_the_same_type() {
+        let check = json!({"type": "one_of", "value": ["pro", 5, 1.5, true]});
+        assert!(passes(check.clone(), json!("pro")));
+        assert!(passes(check.clone(), json!(5)));
+        assert!(passes(check.clone(), json!(1.50)));
+        assert!(passes(check.clone(), json!(true)));
+        assert!(!passes(check.clone(), json!("Pro")));
+        assert!(!passes(check.clone(), json!("5")));
+        assert!(!passes(check.clone(), json!(false)));
+        assert!(!passes(check, json!(null)));
+    }
+
+    #[test]
+    fn bounds_are_inclusive() {
+        let between = json!({"type": "between", "value": ["10", "20.5"]});
+        assert!(passes(between.clone(), json!(10)));
+        assert!(passes(between.clone(), json!(20.5)));
+        assert!(!passes(between.clone(), json!(9.99)));
+        assert!(!passes(between, json!(21)));
+
+        assert!(passes(json!({"type": "gte", "value": 18}), json!(18)));
+        assert!(!passes(json!({"type": "gte", "value": 18}), json!(17)));
+        assert!(passes(json!({"type": "lte", "value": 18}), json!(18)));
+        assert!(passes(json!({"type": "gte_decimal", "value": "0.1"}), json!(0.1)));
+        assert!(passes(json!({"type": "lte_float", "value": 2.5}), json!(2.5)));
+        assert!(passes(json!({"type": "len_gte", "value": 3}), json!("abc")));
+        assert!(!passes(json!({"type": "len_lte", "value": 2}), json!("abc")));
+    }
+
+    #[test]
+    fn dates_are_exclusive() {
+        let after = json!({"type": "after_date", "value": "2024-01-02"});
+        assert!(!passes(after.clone(), json!("2024-01-02")));
+        assert!(!passes(after.clone(), json!(1704153600)));
+        assert!(passes(after.clone(), json!("2024-01-02T00:00:01Z")));
+        assert!(passes(after.clone(), json!(1704153601)));
+
+        let before = json!({"type": "before_date", "value": 1704153600});
+        assert!(!passes(before.clone(), json!("2024-01-02T00:00:00Z")));
+        assert!(passes(before.clone(), json!("2024-01-01")));
+        assert!(passes(before.clone(), json!(1704153599000i64)));
+        assert!(!passes(before, json!("not a date")));
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(Timestamp::parse("2024-01-02T03:04:05Z"), Some(Timestamp(1704164645)));
+        assert_eq!(Timestamp::parse("2024-01-02T05:04:05+02:00"), Some(Timestamp(1704164645)));
+        assert_eq!(Timestamp::parse("2024-01-02"), Some(Timestamp(1704153600)));
+        assert_eq!(Timestamp::parse("1704153600"), Some(Timestamp(1704153600)));
+        // Milliseconds are truncated to seconds
+        assert_eq!(Timestamp::parse("1704153600999"), Some(Timestamp(1704153600)));
+        assert_eq!(Timestamp::parse("2024-13-01"), None);
+        assert_eq!(Timestamp::parse("yesterday"), None);
+
+        assert_eq!(serde_json::from_value::<Timestamp>(json!(1704153600000i64)).unwrap(), Timestamp(1704153600));
+        assert!(serde_json::from_value::<Timestamp>(json!(1.5)).is_err());
+    }
+
+    #[test]
+    fn compares_numeric_strings_as_numbers() {
+        assert!(passes(json!({"type": "gte", "value": 18}), json!("30")));
+        assert!(!passes(json!({"type": "gte", "value": 18}), json!("9")));
+        assert!(passes(json!({"type": "eq_decimal", "value": "12.50"}), json!("12.5")));
+        assert!(passes(json!({"type": "between", "value": [1, 2]}), json!("1.5")));
+        assert!(!passes(json!({"type": "gte", "value": 18}), json!("thirty")));
+        assert!(!passes(json!({"type": "gte", "value": 18}), json!("")));
+    }
+}
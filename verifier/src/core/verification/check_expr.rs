@@ -0,0 +1,234 @@
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+use super::failure::{CheckFailure, FailureReason};
+use super::presentation_check::PresentationCheck;
+use super::context::CheckContext;
+use crate::services::{HandlerOutcome, HandlersManager};
+
+type EvalFuture<'a> = Pin<Box<dyn Future<Output = Result<bool, Box<dyn Error>>> + Send + 'a>>;
+
+// Either a single check or a combination of nested expressions:
+// `{"all": [...]}`, `{"any": [...]}` or `{"not": {...}}`. Unknown keys are
+// rejected so that a typo next to `all` cannot go unnoticed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum CheckExpr {
+    All { all: Vec<CheckExpr> },
+    Any { any: Vec<CheckExpr> },
+    Not { not: Box<CheckExpr> },
+    Check(Box<PresentationCheck>),
+}
+
+impl CheckExpr {
+    pub fn leaves(&self) -> Vec<&PresentationCheck> {
+        match self {
+            CheckExpr::All { all: exprs } | CheckExpr::Any { any: exprs } => {
+                exprs.iter().flat_map(|expr| expr.leaves()).collect()
+            },
+            CheckExpr::Not { not } => not.leaves(),
+            CheckExpr::Check(check) => vec![check.as_ref()],
+        }
+    }
+
+    // Errors (missing windows, handler failures) are never turned into a pass:
+    // `any` only swallows them when another branch succeeds. Whatever made
+    // the expression fail is recorded in `failures`, addressed by `path`.
+    pub fn evaluate<'a>(
+        &'a self,
+        ctx: &'a CheckContext<'a>,
+        path: String,
+        failures: &'a mut Vec<CheckFailure>,
+    ) -> EvalFuture<'a> {
+        Box::pin(async move {
+            match self {
+                CheckExpr::All { all } => {
+                    for (index, expr) in all.iter().enumerate() {
+                        let path = format!("{path}.all[{index}]");
+                        if !expr.evaluate(ctx, path, failures).await? {
+                            return Ok(false);
+                        }
+                    }
+                    Ok(true)
+                },
+                CheckExpr::Any { any } => {
+                    let mark = failures.len();
+                    let mut last_error = None;
+                    for (index, expr) in any.iter().enumerate() {
+                        let path = format!("{path}.any[{index}]");
+                        match expr.evaluate(ctx, path, failures).await {
+                            Ok(true) => {
+                                failures.truncate(mark);
+                                return Ok(true);
+                            },
+                            Ok(false) => {},
+                            Err(err) => last_error = Some(err.to_string()),
+                        }
+                    }
+                    match last_error {
+                        Some(err) => Err(err.into()),
+                        None => Ok(false),
+                    }
+                },
+                CheckExpr::Not { not } => {
+                    let mark = failures.len();
+                    if not.evaluate(ctx, format!("{path}.not"), failures).await? {
+                        failures.push(CheckFailure::new(path, FailureReason::Negated));
+                        Ok(false)
+                    } else {
+                        failures.truncate(mark);
+                        Ok(true)
+                    }
+                },
+                CheckExpr::Check(check) => {
+                    Self::evaluate_check(check, ctx, &path, failures).await
+                },
+            }
+        })
+    }
+
+    async fn evaluate_check(
+        check: &PresentationCheck,
+        ctx: &CheckContext<'_>,
+        path: &str,
+        failures: &mut Vec<CheckFailure>,
+    ) -> Result<bool, Box<dyn Error>> {
+        let Some(data) = check.window.locate(ctx.received) else {
+            error!("missing check window data");
+            failures.push(check.failure(path, FailureReason::MissingWindow));
+            return Err("missing check window data".into());
+        };
+        if check.custom_handler.is_some() {
+            match HandlersManager::execute(check, data, ctx).await {
+                Ok(HandlerOutcome::Rejected(reason)) => {
+                    warn!("custom handler failed check");
+                    failures.push(check.failure(path, FailureReason::HandlerRejected).with_message(reason));
+                    Ok(false)
+                },
+                Ok(_) => Ok(true),
+                Err(err) => {
+                    // The error text may quote the revealed data, so it is only logged
+                    warn!("custom handler error: {err}");
+                    failures.push(check.failure(path, FailureReason::HandlerError));
+                    Err(err)
+                },
+            }
+        } else {
+            let success = check.check(data);
+            if !success {
+                failures.push(CheckFailure {
+                    observed: Some(check.observed_hint(data)),
+                    ..check.failure(path, FailureReason::NotSatisfied)
+                });
+            }
+            Ok(success)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::U256;
+    use serde_json::json;
+    use crate::core::Transcript;
+    use crate::services::Handlers;
+    use super::*;
+
+    // Two revealed windows: #0 `"age": 30` and #1 `"name": "alice"`
+    async fn evaluate(expr: serde_json::Value) -> (Result<bool, String>, Vec<(String, FailureReason)>) {
+        let data = b"\"age\": 30\n\"name\": \"alice\"";
+        let received = Transcript::new(data, [0..9, 10..data.len()].into_iter());
+        let sent = Transcript::new(b"", std::iter::empty());
+        let handlers = Handlers::new();
+        let ctx = CheckContext {
+            server_name: "example.com",
+            credential_group_id: "1",
+            family: 0,
+            app_id: &U256::from(1),
+            sent: &sent,
+            received: &received,
+            handlers: &handlers,
+        };
+        let expr: CheckExpr = serde_json::from_value(expr).unwrap();
+        let mut failures = Vec::new();
+        let result = expr.evaluate(&ctx, "checks".to_string(), &mut failures).await;
+        let failures = failures.into_iter().map(|failure| (failure.check, failure.reason)).collect();
+        (result.map_err(|e| e.to_string()), failures)
+    }
+
+    fn age_gte(value: i64) -> serde_json::Value {
+        json!({"window": {"id": 0, "key": "age"}, "type": "gte", "value": value})
+    }
+
+    fn name_eq(value: &str) -> serde_json::Value {
+        json!({"window": {"id": 1, "key": "name"}, "type": "str_eq", "value": value})
+    }
+
+    fn missing() -> serde_json::Value {
+        json!({"window": {"id": 5, "key": "age"}, "type": "gte", "value": 18})
+    }
+
+    #[tokio::test]
+    async fn all_stops_at_the_first_failure() {
+        let (result, failures) = evaluate(json!({"all": [age_gte(18), name_eq("bob"), age_gte(99)]})).await;
+        assert_eq!(result, Ok(false));
+        assert_eq!(failures, [("checks.all[1]".to_string(), FailureReason::NotSatisfied)]);
+    }
+
+    #[tokio::test]
+    async fn any_drops_the_failures_of_other_branches_once_one_passes() {
+        let (result, failures) = evaluate(json!({"any": [name_eq("bob"), missing(), age_gte(18)]})).await;
+        assert_eq!(result, Ok(true));
+        assert!(failures.is_empty());
+    }
+
+    #[tokio::test]
+    async fn any_keeps_every_failure_when_none_passes() {
+        let (result, failures) = evaluate(json!({"any": [name_eq("bob"), age_gte(99)]})).await;
+        assert_eq!(result, Ok(false));
+        assert_eq!(failures, [
+            ("checks.any[0]".to_string(), FailureReason::NotSatisfied),
+            ("checks.any[1]".to_string(), FailureReason::NotSatisfied),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn any_reports_an_error_when_no_branch_passes() {
+        let (result, failures) = evaluate(json!({"any": [name_eq("bob"), missing()]})).await;
+        assert_eq!(result, Err("missing check window data".to_string()));
+        assert_eq!(failures, [
+            ("checks.any[0]".to_string(), FailureReason::NotSatisfied),
+            ("checks.any[1]".to_string(), FailureReason::MissingWindow),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn not_records_only_the_negation() {
+        let (result, failures) = evaluate(json!({"not": name_eq("alice")})).await;
+        assert_eq!(result, Ok(false));
+        assert_eq!(failures, [("checks".to_string(), FailureReason::Negated)]);
+
+        let (result, failures) = evaluate(json!({"not": name_eq("bob")})).await;
+        assert_eq!(result, Ok(true));
+        assert!(failures.is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_next_to_a_combinator() {
+        let parse = |expr| serde_json::from_value::<CheckExpr>(expr).is_ok();
+        assert!(parse(json!({"all": [age_gte(18)]})));
+        assert!(!parse(json!({"all": [age_gte(18)], "any": [name_eq("bob")]})));
+        assert!(!parse(json!({"any": [age_gte(18)], "al": [name_eq("bob")]})));
+        assert!(!parse(json!({"not": name_eq("bob"), "comment": "typo"})));
+        assert!(!parse(json!({"al": [age_gte(18)]})));
+    }
+
+    #[tokio::test]
+    async fn not_does_not_turn_an_error_into_a_pass() {
+        let (result, failures) = evaluate(json!({"not": missing()})).await;
+        assert!(result.is_err());
+        assert_eq!(failures, [("checks.not".to_string(), FailureReason::MissingWindow)]);
+    }
+}
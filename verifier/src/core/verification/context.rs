@@ -0,0 +1,16 @@
+use alloy::primitives::U256;
+use crate::core::Transcript;
+use crate::services::Handlers;
+
+// The presentation a verification runs against, shared by all of its
+// checks and passed on to custom handlers
+#[derive(Clone, Copy)]
+pub struct CheckContext<'a> {
+    pub server_name: &'a str,
+    pub credential_group_id: &'a str,
+    pub family: u32,
+    pub app_id: &'a U256,
+    pub sent: &'a Transcript,
+    pub received: &'a Transcript,
+    pub handlers: &'a Handlers,
+}
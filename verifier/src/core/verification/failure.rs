@@ -0,0 +1,95 @@
+use std::error::Error;
+use std::fmt;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    WrongHost,
+    RequestMismatch,
+    ResponseMismatch,
+    MissingWindow,
+    NotSatisfied,
+    // The check inside a `not` passed
+    Negated,
+    HandlerRejected,
+    HandlerError,
+}
+
+// One failed check, safe to return to the client: `observed` only
+// describes the shape of the revealed value, never the value itself
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckFailure {
+    pub check: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub reason: FailureReason,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub observed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl CheckFailure {
+    pub fn new(check: impl Into<String>, reason: FailureReason) -> Self {
+        Self {
+            check: check.into(),
+            name: None,
+            reason,
+            expected: None,
+            observed: None,
+            message: None,
+        }
+    }
+
+    pub fn with_message(mut self, message: impl fmt::Display) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+}
+
+impl fmt::Display for CheckFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.check)?;
+        if let Some(name) = &self.name {
+            write!(f, " ({name})")?;
+        }
+        write!(f, ": {:?}", self.reason)?;
+        if let Some(expected) = &self.expected {
+            write!(f, ", expected {expected}")?;
+        }
+        if let Some(observed) = &self.observed {
+            write!(f, ", observed {observed}")?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct VerificationFailed {
+    pub failures: Vec<CheckFailure>,
+}
+
+impl From<CheckFailure> for VerificationFailed {
+    fn from(failure: CheckFailure) -> Self {
+        Self { failures: vec![failure] }
+    }
+}
+
+impl fmt::Display for VerificationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "verification failed")?;
+        for failure in &self.failures {
+            write!(f, "; {failure}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for VerificationFailed {}
@@ -0,0 +1,157 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+// Selector such as `data.user.id`, `devices[0].id` or `activities[*].description`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct JsonPath {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        for part in raw.split('.') {
+            let (key, mut rest) = match part.find('[') {
+                Some(pos) => part.split_at(pos),
+                None => (part, ""),
+            };
+            if key.is_empty() && rest.is_empty() {
+                return Err(format!("empty segment in path {raw}"));
+            }
+            if !key.is_empty() {
+                segments.push(Segment::Key(key.to_string()));
+            }
+            while !rest.is_empty() {
+                let Some(end) = rest.find(']').filter(|_| rest.starts_with('[')) else {
+                    return Err(format!("malformed index in path {raw}"));
+                };
+                let index = &rest[1..end];
+                if index == "*" {
+                    segments.push(Segment::Wildcard);
+                } else {
+                    let index = index.parse::<usize>()
+                        .map_err(|_| format!("invalid index {index} in path {raw}"))?;
+                    segments.push(Segment::Index(index));
+                }
+                rest = &rest[end + 1..];
+            }
+        }
+        Ok(Self { raw: raw.to_string(), segments })
+    }
+
+    pub fn key(key: &str) -> Self {
+        Self {
+            raw: key.to_string(),
+            segments: vec![Segment::Key(key.to_string())],
+        }
+    }
+
+    // Wildcards collect every match into an array, so a path
+    // with a wildcard always selects a JSON array.
+    pub fn select(&self, root: &Value) -> Option<Value> {
+        Self::select_from(&self.segments, root)
+    }
+
+    fn select_from(segments: &[Segment], value: &Value) -> Option<Value> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return Some(value.clone());
+        };
+        match segment {
+            Segment::Key(key) => Self::select_from(rest, value.as_object()?.get(key)?),
+            Segment::Index(index) => Self::select_from(rest, value.as_array()?.get(*index)?),
+            Segment::Wildcard => {
+                let items = value
+                    .as_array()?
+                    .iter()
+                    .filter_map(|item| Self::select_from(rest, item))
+                    .flat_map(|item| match item {
+                        Value::Array(nested) if rest.contains(&Segment::Wildcard) => nested,
+                        other => vec![other],
+                    })
+                    .collect();
+                Some(Value::Array(items))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for JsonPath {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<JsonPath> for String {
+    fn from(value: JsonPath) -> Self {
+        value.raw
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::core::verification::window::parse_fragment;
+    use super::*;
+
+    fn select(path: &str, root: &Value) -> Option<Value> {
+        JsonPath::parse(path).unwrap().select(root)
+    }
+
+    #[test]
+    fn selects_nested_keys_and_indices() {
+        let root = json!({"data": {"user": {"id": 42}}, "devices": [{"id": "a"}, {"id": "b"}]});
+        assert_eq!(select("data.user.id", &root), Some(json!(42)));
+        assert_eq!(select("data.user", &root), Some(json!({"id": 42})));
+        assert_eq!(select("devices[1].id", &root), Some(json!("b")));
+        assert_eq!(select("devices[*].id", &root), Some(json!(["a", "b"])));
+    }
+
+    #[test]
+    fn missing_paths_select_nothing() {
+        let root = json!({"data": {"user": {"id": 42}}, "devices": [{"id": "a"}]});
+        assert_eq!(select("data.account.id", &root), None);
+        assert_eq!(select("devices[1].id", &root), None);
+        // Indexing an object or keying into an array does not match either
+        assert_eq!(select("data[0]", &root), None);
+        assert_eq!(select("devices.id", &root), None);
+        assert_eq!(select("devices[*].name", &root), Some(json!([])));
+    }
+
+    #[test]
+    fn rejects_malformed_paths() {
+        assert!(JsonPath::parse("data..id").is_err());
+        assert!(JsonPath::parse("devices[x]").is_err());
+        assert!(JsonPath::parse("devices[0").is_err());
+        assert!(JsonPath::parse("devices[0]x").is_err());
+    }
+
+    #[test]
+    fn selects_from_a_partly_revealed_fragment() {
+        let fragment = parse_fragment(r#""user": {"id": 42, "tags": ["a", "b"]}, "plan": "pro","#).unwrap();
+        assert_eq!(select("user.id", &fragment), Some(json!(42)));
+        assert_eq!(select("user.tags[1]", &fragment), Some(json!("b")));
+        assert_eq!(select("plan", &fragment), Some(json!("pro")));
+
+        let fragment = parse_fragment(r#"{"user": {"id": 42}, "plan": "pro""#).unwrap();
+        assert_eq!(select("user.id", &fragment), Some(json!(42)));
+        assert_eq!(parse_fragment(r#""user": {"id": 4"#), None);
+    }
+}
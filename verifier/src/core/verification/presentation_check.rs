@@ -1,49 +1,144 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use serde_json::{Value};
-use super::check::{Check, CheckableValue};
-use super::window::Window;
+use serde_json::Value;
+use super::aggregate::Aggregate;
+use super::check::{Check, CheckableValue, ValueKind};
+use super::failure::{CheckFailure, FailureReason};
+use super::window::{parse_fragment, Window};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PresentationCheck {
+    // Shown to the client when the check fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     pub window: Window,
     #[serde(flatten)]
     pub check: Check,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregate: Option<Aggregate>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub custom_handler: Option<String>,
-
+    // Passed as is to the custom handler
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub handler_params: Value,
 }
 
 impl PresentationCheck {
     pub fn check(&self, transcript: &str) -> bool {
-        if self.window.key != "-" {
-            if !transcript.trim_matches(' ').starts_with(&format!("\"{}\"", self.window.key)) {
-                return false;
-            }
-            let Some(value) = transcript
-                .split_once(':')
-                .map(|(_, val)| val.trim_matches('"'))
-            else {
-                return false;
+        if self.aggregate.is_some() {
+            return self.aggregated(transcript).is_some_and(|value| self.check_value(value));
+        }
+        if self.window.selector().is_none() {
+            return self.check_value(transcript);
+        }
+        match self.window.select(transcript) {
+            Some(value) => self.check_value(&value),
+            None => false,
+        }
+    }
+
+    // The value the check is applied to, for diagnostics
+    pub fn observed(&self, transcript: &str) -> String {
+        if self.aggregate.is_some() {
+            return match self.aggregated(transcript) {
+                Some(value) => value.to_string(),
+                None => "<no array to aggregate>".to_string(),
             };
+        }
+        if self.window.selector().is_none() {
+            return transcript.to_string();
+        }
+        match self.window.select(transcript) {
+            Some(value) => value.to_string(),
+            None => "<path not found>".to_string(),
+        }
+    }
 
-            // Trying to parse JSON array
-            if let Ok(json_value) = serde_json::from_str::<Value>(value) {
-                if let Some(array) = json_value.as_array() {
-                    return self.check_value(array);
-                }
+    // Expected condition as configured, including the aggregate if any
+    pub fn expected(&self) -> Value {
+        let mut expected = serde_json::to_value(&self.check).unwrap_or(Value::Null);
+        if let Value::Object(map) = &mut expected {
+            if let Some(aggregate) = &self.aggregate {
+                map.insert(
+                    "aggregate".to_string(),
+                    serde_json::to_value(aggregate).unwrap_or(Value::Null),
+                );
             }
-
-            // Trying to parse i64, if error - process &str
-            match value.parse::<i64>() {
-                Ok(num) => self.check_value(num),
-                Err(_) => self.check_value(value),
+            if !self.handler_params.is_null() {
+                map.insert("handler_params".to_string(), self.handler_params.clone());
             }
-        } else {
-            self.check_value(transcript)
+        }
+        expected
+    }
+
+    // Shape of the value the check was applied to, without its content.
+    // Counts are the only values shown, e.g. the number of matching rides.
+    pub fn observed_hint(&self, transcript: &str) -> String {
+        if let Some(aggregate) = &self.aggregate {
+            return match self.aggregated(transcript) {
+                Some(value) if aggregate.is_count() => format!("number {value}"),
+                Some(_) => "number".to_string(),
+                None => "not an array".to_string(),
+            };
+        }
+        if self.window.selector().is_none() {
+            return format!("text ({} chars)", transcript.chars().count());
+        }
+        match self.window.select(transcript) {
+            Some(Value::Number(_)) => "number".to_string(),
+            Some(Value::String(string)) => format!("string ({} chars)", string.chars().count()),
+            Some(Value::Array(array)) => format!("array ({} items)", array.len()),
+            Some(Value::Object(_)) => "object".to_string(),
+            Some(Value::Bool(_)) => "bool".to_string(),
+            Some(Value::Null) => "null".to_string(),
+            None => "missing".to_string(),
+        }
+    }
+
+    pub fn failure(&self, path: &str, reason: FailureReason) -> CheckFailure {
+        CheckFailure {
+            name: self.name.clone(),
+            expected: Some(self.expected()),
+            ..CheckFailure::new(path, reason)
+        }
+    }
+
+    fn aggregated(&self, transcript: &str) -> Option<Decimal> {
+        let aggregate = self.aggregate.as_ref()?;
+        let items = match self.window.selector() {
+            Some(_) => self.window.select(transcript),
+            None => parse_fragment(transcript),
+        };
+        match items {
+            Some(Value::Array(items)) => aggregate.evaluate(&items),
+            _ => None,
         }
     }
 
     pub fn check_value<T: CheckableValue>(&self, value: T) -> bool {
         value.check_against(&self.check)
     }
+
+    // A field selected with `key` or `path` can be any JSON value and is
+    // only known at verification time, so it is never linted
+    pub fn value_kind(&self) -> Option<ValueKind> {
+        if self.aggregate.is_some() {
+            return Some(ValueKind::Number);
+        }
+        match self.window.selector() {
+            Some(_) => None,
+            None => Some(ValueKind::Text),
+        }
+    }
+
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = self.check.problems(self.value_kind());
+        if matches!(self.check, Check::Custom) && self.custom_handler.is_none() {
+            problems.push("`custom` check without a custom_handler".to_string());
+        }
+        if !self.handler_params.is_null() && self.custom_handler.is_none() {
+            problems.push("handler_params without a custom_handler".to_string());
+        }
+        problems
+    }
 }
\ No newline at end of file
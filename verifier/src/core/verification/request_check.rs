@@ -0,0 +1,139 @@
+use std::error::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+use tracing::error;
+use super::check::Pattern;
+use crate::core::Transcript;
+
+// Expected properties of the request the proven response belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestCheck {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+    // Has to match the whole path, not just a part of it
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "anchored")]
+    pub path_regex: Option<Pattern>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<String>,
+}
+
+impl RequestCheck {
+    pub fn check(&self, sent: &Transcript) -> Result<(), Box<dyn Error>> {
+        // The whole request line has to be revealed, otherwise
+        // the path could be cut right before the part that matters
+        let request_line = sent
+            .text_at(0)
+            .and_then(|text| text.split_once("\r\n"))
+            .map(|(line, _)| line)
+            .ok_or_else(|| {
+                error!("request line is not revealed");
+                "request line is not revealed"
+            })?;
+
+        let mut parts = request_line.split(' ');
+        let (Some(method), Some(target), Some(_version), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            error!("malformed request line");
+            return Err("malformed request line".into());
+        };
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        // The server resolves these, so `/v1/me/../other` would
+        // pass a `/v1/me` prefix while reaching another endpoint
+        if !is_normalized(path) {
+            error!("request path is not normalized");
+            return Err("request path has dot segments or encoded separators".into());
+        }
+
+        if let Some(expected) = &self.method
+            && !method.eq_ignore_ascii_case(expected)
+        {
+            error!("wrong request method");
+            return Err(format!("wrong request method, expected {expected}").into());
+        }
+        if let Some(prefix) = &self.path_prefix
+            && !path.starts_with(prefix.as_str())
+        {
+            error!("wrong request path");
+            return Err(format!("wrong request path, expected prefix {prefix}").into());
+        }
+        if let Some(pattern) = &self.path_regex
+            && !pattern.is_match(path)
+        {
+            error!("wrong request path");
+            return Err(format!("wrong request path, expected to match {}", pattern.as_str()).into());
+        }
+        for name in &self.query {
+            let present = query
+                .split('&')
+                .any(|param| param.split_once('=').map_or(param, |(key, _)| key) == name);
+            if !present {
+                error!("missing query parameter {name}");
+                return Err(format!("missing query parameter {name}").into());
+            }
+        }
+        for name in &self.headers {
+            if sent.header(name).is_none() {
+                error!("missing request header {name}");
+                return Err(format!("missing request header {name}").into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn anchored<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Pattern>, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Pattern::anchored(&pattern)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn is_normalized(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    !path.contains('\\')
+        && !["%2e", "%2f", "%5c"].iter().any(|encoded| lower.contains(encoded))
+        && !path.split('/').any(|segment| segment == "." || segment == "..")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn check(request_check: serde_json::Value, request_line: &str) -> Result<(), String> {
+        let request_check: RequestCheck = serde_json::from_value(request_check).unwrap();
+        let data = format!("{request_line}\r\nHost: api.example.com\r\n\r\n");
+        let sent = Transcript::new(data.as_bytes(), std::iter::once(0..data.len()));
+        request_check.check(&sent).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn path_prefix() {
+        let prefix = json!({"method": "GET", "path_prefix": "/v1/me"});
+        assert_eq!(check(prefix.clone(), "GET /v1/me/rides?page=2 HTTP/1.1"), Ok(()));
+        assert!(check(prefix.clone(), "GET /v1/other HTTP/1.1").is_err());
+        assert!(check(prefix.clone(), "POST /v1/me HTTP/1.1").is_err());
+    }
+
+    #[test]
+    fn rejects_paths_the_server_would_resolve_elsewhere() {
+        let prefix = json!({"path_prefix": "/v1/me"});
+        for target in ["/v1/me/../../other", "/v1/me/./x", "/v1/me/%2e%2e/other", "/v1/me%2F..%2Fother", "/v1/me\\..\\other"] {
+            assert!(check(prefix.clone(), &format!("GET {target} HTTP/1.1")).is_err(), "{target}");
+        }
+        assert_eq!(check(prefix, "GET /v1/me/a..b HTTP/1.1"), Ok(()));
+    }
+
+    #[test]
+    fn path_regex_matches_the_whole_path() {
+        let regex = json!({"path_regex": "/v1/users/[0-9]+"});
+        assert_eq!(check(regex.clone(), "GET /v1/users/42 HTTP/1.1"), Ok(()));
+        assert!(check(regex.clone(), "GET /v1/users/42/delete HTTP/1.1").is_err());
+        assert!(check(regex, "GET /admin/v1/users/42 HTTP/1.1").is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use super::check::{Check, CheckableValue, ValueKind};
+use crate::core::Transcript;
+
+// Expected status and headers of the proven response, so that a redirect
+// or an error page containing the right substring is not accepted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCheck {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, Check>,
+}
+
+impl ResponseCheck {
+    pub fn problems(&self) -> Vec<String> {
+        self.headers
+            .iter()
+            .flat_map(|(name, check)| {
+                check
+                    .problems(Some(ValueKind::Text))
+                    .into_iter()
+                    .map(move |problem| format!("response header {name}: {problem}"))
+            })
+            .collect()
+    }
+
+    pub fn check(&self, received: &Transcript) -> Result<(), Box<dyn Error>> {
+        let http = received.http().ok_or_else(|| {
+            error!("response headers are not revealed");
+            "response headers are not revealed"
+        })?;
+
+        if let Some(expected) = self.status {
+            let status = http.status().ok_or_else(|| {
+                error!("response status is not revealed");
+                "response status is not revealed"
+            })?;
+            if status != expected {
+                error!("wrong response status");
+                return Err(format!("wrong response status, expected {expected}").into());
+            }
+        }
+
+        for (name, check) in &self.headers {
+            let Some(value) = http.header(name) else {
+                error!("missing response header {name}");
+                return Err(format!("missing response header {name}").into());
+            };
+            if !value.check_against(check) {
+                error!("wrong response header {name}");
+                return Err(format!("wrong response header {name}").into());
+            }
+        }
+
+        Ok(())
+    }
+}
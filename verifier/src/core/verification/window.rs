@@ -1,8 +1,168 @@
+use std::fmt;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use super::json_path::JsonPath;
+use crate::core::Transcript;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[derive(Clone)]
 pub struct Window {
-    pub(crate) id: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) id: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) anchor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) part: Option<Part>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) offset: Option<usize>,
+    #[serde(default = "raw_key")]
     pub(crate) key: String,
-}
\ No newline at end of file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) path: Option<JsonPath>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Part {
+    Body,
+    Header(String),
+}
+
+fn raw_key() -> String {
+    "-".to_string()
+}
+
+// Which addressing mode a window was resolved with
+#[derive(Debug, Clone, Copy)]
+pub enum Located<'w> {
+    Anchor(&'w str),
+    Header(&'w str),
+    Body(usize),
+    Range(usize),
+}
+
+impl fmt::Display for Located<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Located::Anchor(anchor) => write!(f, "anchor {anchor}"),
+            Located::Header(name) => write!(f, "header {name}"),
+            Located::Body(offset) => write!(f, "body at offset {offset}"),
+            Located::Range(id) => write!(f, "authed range #{id}"),
+        }
+    }
+}
+
+impl Window {
+    pub fn locate<'a>(&self, transcript: &'a Transcript) -> Option<&'a str> {
+        self.locate_with_source(transcript).map(|(_, data)| data)
+    }
+
+    // Content-based addressing (anchor, then HTTP part / body offset)
+    // is tried first, the authed range index is only a fallback. A
+    // configured anchor is never skipped: when it is not revealed, the
+    // range at `id` holds something else.
+    pub fn locate_with_source<'w, 'a>(
+        &'w self,
+        transcript: &'a Transcript,
+    ) -> Option<(Located<'w>, &'a str)> {
+        if let Some(anchor) = &self.anchor {
+            return transcript.find_anchor(anchor).map(|data| (Located::Anchor(anchor.as_str()), data));
+        }
+        let located = match (&self.part, self.offset) {
+            (Some(Part::Header(name)), _) => transcript
+                .header(name)
+                .map(|data| (Located::Header(name), data)),
+            (Some(Part::Body), offset) => Self::body_at(transcript, offset.unwrap_or_default())
+                .map(|data| (Located::Body(offset.unwrap_or_default()), data)),
+            (None, Some(offset)) => Self::body_at(transcript, offset)
+                .map(|data| (Located::Body(offset), data)),
+            (None, None) => None,
+        };
+        located.or_else(|| {
+            let id = self.id?;
+            Some((Located::Range(id), transcript.get(id)?))
+        })
+    }
+
+    fn body_at(transcript: &Transcript, offset: usize) -> Option<&str> {
+        match transcript.body() {
+            Some(body) => body.get(offset..),
+            None => transcript.text_at(transcript.body_start()? + offset),
+        }
+    }
+
+    // `path` takes precedence, a legacy `key` selects a top-level field,
+    // and `"-"` means the window is checked as raw text.
+    pub fn selector(&self) -> Option<JsonPath> {
+        match &self.path {
+            Some(path) => Some(path.clone()),
+            None if self.key != "-" => Some(JsonPath::key(&self.key)),
+            None => None,
+        }
+    }
+
+    pub fn select(&self, data: &str) -> Option<Value> {
+        let selector = self.selector()?;
+        selector.select(&parse_fragment(data)?)
+    }
+}
+
+// Revealed ranges are usually object members (`"key": value, ...`)
+// rather than whole documents, so those are wrapped into an object.
+pub fn parse_fragment(data: &str) -> Option<Value> {
+    let data = data.trim();
+    if let Ok(value) = serde_json::from_str::<Value>(data) {
+        return Some(value);
+    }
+    let members = data.trim_matches(|c: char| c == ',' || c.is_whitespace());
+    let members = [
+        members,
+        members.strip_prefix('{').unwrap_or(members),
+        members.strip_suffix('}').unwrap_or(members),
+    ];
+    members
+        .iter()
+        .find_map(|members| serde_json::from_str::<Value>(&format!("{{{members}}}")).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    // Two revealed ranges: #0 `"name": "alice"` and #1 `"uuid": "u-1"`
+    fn transcript() -> Transcript {
+        let data = b"\"name\": \"alice\"\n\"uuid\": \"u-1\"";
+        Transcript::new(data, [0..15, 16..data.len()].into_iter())
+    }
+
+    fn window(window: Value) -> Window {
+        serde_json::from_value(window).unwrap()
+    }
+
+    #[test]
+    fn anchor_takes_precedence_over_the_index() {
+        let transcript = transcript();
+        let window = window(json!({"id": 0, "anchor": "\"uuid\"", "key": "uuid"}));
+        let (located, data) = window.locate_with_source(&transcript).unwrap();
+        assert!(matches!(located, Located::Anchor("\"uuid\"")));
+        assert_eq!(window.select(data), Some(json!("u-1")));
+    }
+
+    #[test]
+    fn missing_anchor_does_not_fall_back_to_the_index() {
+        let transcript = transcript();
+        let window = window(json!({"id": 0, "anchor": "\"email\"", "key": "email"}));
+        assert!(window.locate_with_source(&transcript).is_none());
+    }
+
+    #[test]
+    fn index_is_used_without_an_anchor() {
+        let transcript = transcript();
+        let by_index = window(json!({"id": 1}));
+        let (located, data) = by_index.locate_with_source(&transcript).unwrap();
+        assert!(matches!(located, Located::Range(1)));
+        assert_eq!(data, "\"uuid\": \"u-1\"");
+        assert!(window(json!({"id": 2})).locate_with_source(&transcript).is_none());
+    }
+}
@@ -1,7 +1,34 @@
 pub mod apple_devices_user_id;
 mod apple_subs_user_id;
 mod uber_rides_amount;
+mod wasm;
 
-pub use apple_devices_user_id::handler as apple_devices_user_id;
-pub use apple_subs_user_id::handler as apple_subs_user_id;
-pub use uber_rides_amount::handler as uber_rides_amount;
\ No newline at end of file
+use std::error::Error;
+use std::sync::Arc;
+pub use apple_devices_user_id::AppleDevicesUserId;
+pub use apple_subs_user_id::AppleSubsUserId;
+pub use uber_rides_amount::UberRidesAmount;
+
+use crate::services::Handlers;
+
+fn builtin_handlers() -> Handlers {
+    let mut handlers = Handlers::new();
+    handlers.insert("apple_devices_user_id".to_string(), Arc::new(AppleDevicesUserId));
+    handlers.insert("uber_rides_amount".to_string(), Arc::new(UberRidesAmount));
+    handlers.insert("apple_subs_user_id".to_string(), Arc::new(AppleSubsUserId));
+    handlers
+}
+
+// Built-in handlers plus the WASM modules listed in WASM_HANDLERS_FILE
+pub fn load_all() -> Result<Handlers, Box<dyn Error>> {
+    let mut handlers = builtin_handlers();
+    if let Some(path) = wasm::wasm_handlers_path() {
+        for (name, handler) in wasm::load_wasm_handlers(&path)? {
+            if handlers.contains_key(&name) {
+                return Err(format!("WASM handler {name} would replace a built-in handler").into());
+            }
+            handlers.insert(name, handler);
+        }
+    }
+    Ok(handlers)
+}
@@ -1,10 +1,8 @@
 use std::error::Error;
-use alloy::primitives::{keccak256, B256, U256};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tracing::debug;
-use crate::config;
-use crate::core::{PresentationCheck};
+use crate::services::{parse_params, CustomHandler, HandlerContext, HandlerFuture, HandlerOutcome};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct DeviceData {
@@ -13,13 +11,29 @@ struct DeviceData {
     extra: std::collections::HashMap<String, Value>,
 }
 
-pub fn handler(_: &PresentationCheck, transcript: &str, app_id: &U256) -> Result<(bool, Option<B256>), Box<dyn Error>> {
-    let (key, value) = transcript
+// Key of the revealed `"key": value` pair holding the user id
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    key: String,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self { key: "devices".to_string() }
+    }
+}
+
+pub struct AppleDevicesUserId;
+
+fn outcome(ctx: &HandlerContext) -> Result<HandlerOutcome, Box<dyn Error + Send + Sync>> {
+    let params: Params = parse_params(ctx.params)?;
+    let (key, value) = ctx.data
         .split_once(":")
         .ok_or("Wrong transcript provided")?;
 
-    if key.trim() != "\"devices\"" {
-        return Err("Wrong transcript provided - \"devices\" key was not found".into());
+    if key.trim() != format!("\"{}\"", params.key) {
+        return Err(format!("Wrong transcript provided - \"{}\" key was not found", params.key).into());
     }
     let items: Vec<DeviceData> = serde_json::from_str(value)?;
 
@@ -28,15 +42,16 @@ pub fn handler(_: &PresentationCheck, transcript: &str, app_id: &U256) -> Result
         .id
         .clone();
     debug!("Apple UserID: {}", user_id);
-    let user_id = user_id.as_bytes();
 
-    let private_key_bytes = config::get().private_key.to_bytes();
-    let mut buf = Vec::new();
-    buf.extend_from_slice(user_id);
-    buf.extend_from_slice(&app_id.to_be_bytes::<32>());
-    buf.extend_from_slice(&private_key_bytes);
+    Ok(HandlerOutcome::Identified(user_id.into_bytes()))
+}
 
-    let credential_id = keccak256(&buf);
+impl CustomHandler for AppleDevicesUserId {
+    fn execute<'a>(&'a self, ctx: HandlerContext<'a>) -> HandlerFuture<'a> {
+        Box::pin(async move { outcome(&ctx) })
+    }
 
-    Ok((true, Some(credential_id)))
+    fn validate_params(&self, params: &Value) -> Result<(), String> {
+        parse_params::<Params>(params).map(|_| ())
+    }
 }
@@ -1,29 +1,46 @@
 use std::error::Error;
-use alloy::primitives::{keccak256, B256, U256};
+use serde::Deserialize;
+use serde_json::Value;
 use tracing::debug;
-use crate::config;
-use crate::core::PresentationCheck;
+use crate::services::{parse_params, CustomHandler, HandlerContext, HandlerFuture, HandlerOutcome};
 
-pub fn handler(_: &PresentationCheck, transcript: &str, app_id: &U256) -> Result<(bool, Option<B256>), Box<dyn Error>> {
-    let (key, value) = transcript
+// Key of the revealed `"key": value` pair holding the user id
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    key: String,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self { key: "subscriptionId".to_string() }
+    }
+}
+
+pub struct AppleSubsUserId;
+
+fn outcome(ctx: &HandlerContext) -> Result<HandlerOutcome, Box<dyn Error + Send + Sync>> {
+    let params: Params = parse_params(ctx.params)?;
+    let (key, value) = ctx.data
         .split_once(":")
         .ok_or("Wrong transcript provided")?;
 
-    if key.trim() != "\"subscriptionId\"" {
-        return Err("Wrong transcript provided - \"subscriptionId\" key was not found".into());
+    if key.trim() != format!("\"{}\"", params.key) {
+        return Err(format!("Wrong transcript provided - \"{}\" key was not found", params.key).into());
     }
 
     let subscription_id = value.trim().trim_matches('"');
     debug!("Apple Subscription ID: {}", subscription_id);
-    let subscription_id = subscription_id.as_bytes();
 
-    let private_key_bytes = config::get().private_key.to_bytes();
-    let mut buf = Vec::new();
-    buf.extend_from_slice(subscription_id);
-    buf.extend_from_slice(&app_id.to_be_bytes::<32>());
-    buf.extend_from_slice(&private_key_bytes);
+    Ok(HandlerOutcome::Identified(subscription_id.as_bytes().to_vec()))
+}
 
-    let credential_id = keccak256(&buf);
+impl CustomHandler for AppleSubsUserId {
+    fn execute<'a>(&'a self, ctx: HandlerContext<'a>) -> HandlerFuture<'a> {
+        Box::pin(async move { outcome(&ctx) })
+    }
 
-    Ok((true, Some(credential_id)))
+    fn validate_params(&self, params: &Value) -> Result<(), String> {
+        parse_params::<Params>(params).map(|_| ())
+    }
 }
@@ -1,7 +1,7 @@
 use std::error::Error;
-use alloy::primitives::{B256, U256};
 use serde::{Deserialize, Serialize};
-use crate::core::{PresentationCheck};
+use serde_json::Value;
+use crate::services::{parse_params, CustomHandler, HandlerContext, HandlerFuture, HandlerOutcome};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct RideData {
@@ -9,24 +9,56 @@ struct RideData {
     description: String,
 }
 
-pub fn handler(_: &PresentationCheck, transcript: &str, _app_id: &U256) -> Result<(bool, Option<B256>), Box<dyn Error>> {
-    let (key, value) = transcript
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    key: String,
+    min_rides: u64,
+    // Rides whose description contains any of these are not counted
+    exclude: Vec<String>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            key: "activities".to_string(),
+            min_rides: 5,
+            exclude: vec!["Canceled".to_string()],
+        }
+    }
+}
+
+pub struct UberRidesAmount;
+
+fn outcome(ctx: &HandlerContext) -> Result<HandlerOutcome, Box<dyn Error + Send + Sync>> {
+    let params: Params = parse_params(ctx.params)?;
+    let (key, value) = ctx.data
         .split_once(":")
         .ok_or("Wrong transcript provided")?;
 
-    if key.trim() != "\"activities\"" {
-        return Err("Wrong transcript provided - \"activities\" key was not found".into());
+    if key.trim() != format!("\"{}\"", params.key) {
+        return Err(format!("Wrong transcript provided - \"{}\" key was not found", params.key).into());
     }
     let rides: Vec<RideData> = serde_json::from_str(value)?;
 
     let valid_rides_count = rides
         .iter()
-        .filter(|ride| !ride.description.contains("Canceled"))
-        .count();
+        .filter(|ride| !params.exclude.iter().any(|excluded| ride.description.contains(excluded.as_str())))
+        .count() as u64;
 
-    if valid_rides_count < 5 {
-        return Ok((false, None))
+    if valid_rides_count < params.min_rides {
+        return Ok(HandlerOutcome::Rejected(format!("{valid_rides_count} completed rides, at least {} required", params.min_rides)))
     }
 
-    Ok((true, None))
+    Ok(HandlerOutcome::Passed)
+}
+
+impl CustomHandler for UberRidesAmount {
+    fn execute<'a>(&'a self, ctx: HandlerContext<'a>) -> HandlerFuture<'a> {
+        Box::pin(async move { outcome(&ctx) })
+    }
+
+    fn validate_params(&self, params: &Value) -> Result<(), String> {
+        parse_params::<Params>(params).map(|_| ())
+    }
 }
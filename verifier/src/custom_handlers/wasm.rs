@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use serde::Deserialize;
+use tracing::info;
+use wasmtime::{Caller, Config, Engine, InstancePre, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder};
+use crate::services::{CustomHandler, HandlerContext, HandlerFuture, HandlerOutcome, Handlers};
+
+const HOST_MODULE: &str = "bringid";
+const DEFAULT_FUEL: u64 = 10_000_000;
+const DEFAULT_MAX_MEMORY: usize = 16 * 1024 * 1024;
+const MAX_USER_ID_LEN: usize = 256;
+// Host memory a table can take is not covered by `max_memory`
+const MAX_TABLE_ELEMENTS: usize = 10_000;
+
+// `input_len` / `input_read` sources besides the received windows 0..
+const INPUT_DATA: i32 = -1;
+const INPUT_PARAMS: i32 = -2;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WasmHandlerEntry {
+    path: String,
+    #[serde(default = "default_fuel")]
+    fuel: u64,
+    #[serde(default = "default_max_memory")]
+    max_memory: usize,
+}
+
+fn default_fuel() -> u64 {
+    DEFAULT_FUEL
+}
+
+fn default_max_memory() -> usize {
+    DEFAULT_MAX_MEMORY
+}
+
+// Everything a module can see or produce during one call
+struct HostState {
+    data: String,
+    params: String,
+    windows: Vec<String>,
+    user_id: Option<Vec<u8>>,
+    limits: StoreLimits,
+}
+
+impl HostState {
+    fn input(&self, source: i32) -> Option<&[u8]> {
+        match source {
+            INPUT_DATA => Some(self.data.as_bytes()),
+            INPUT_PARAMS => Some(self.params.as_bytes()),
+            index => self.windows.get(usize::try_from(index).ok()?).map(|window| window.as_bytes()),
+        }
+    }
+}
+
+// What `check` returned, with the user id the module set if it passed
+enum Verdict {
+    Passed(Option<Vec<u8>>),
+    Rejected,
+}
+
+struct WasmModule {
+    name: String,
+    instance: InstancePre<HostState>,
+    fuel: u64,
+    max_memory: usize,
+}
+
+// A partner check compiled to WASM. The module exports `memory` and
+// `check() -> i32` (1 pass, 0 reject) and can only import the functions
+// of `linker`: it reads its inputs and may hand back a user id, which the
+// core turns into a credential id like for any other handler.
+pub struct WasmHandler(Arc<WasmModule>);
+
+fn memory(caller: &mut Caller<'_, HostState>) -> wasmtime::Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| wasmtime::Error::msg("module does not export memory"))
+}
+
+fn guest_range(ptr: i32, len: i32) -> std::ops::Range<usize> {
+    let start = ptr as u32 as usize;
+    start..start.saturating_add(len as u32 as usize)
+}
+
+fn linker(engine: &Engine) -> wasmtime::Result<Linker<HostState>> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap(HOST_MODULE, "input_len", |caller: Caller<'_, HostState>, source: i32| -> i32 {
+        match caller.data().input(source) {
+            Some(input) => input.len() as i32,
+            None => -1,
+        }
+    })?;
+    // Copies up to `len` bytes of the input, returns how many or -1
+    linker.func_wrap(
+        HOST_MODULE,
+        "input_read",
+        |mut caller: Caller<'_, HostState>, source: i32, ptr: i32, len: i32| -> wasmtime::Result<i32> {
+            let memory = memory(&mut caller)?;
+            let (memory, state) = memory.data_and_store_mut(&mut caller);
+            let Some(input) = state.input(source) else {
+                return Ok(-1);
+            };
+            let count = input.len().min(len as u32 as usize);
+            let range = guest_range(ptr, count as i32);
+            let dest = memory
+                .get_mut(range)
+                .ok_or_else(|| wasmtime::Error::msg("input_read out of bounds"))?;
+            dest.copy_from_slice(&input[..count]);
+            Ok(count as i32)
+        },
+    )?;
+    linker.func_wrap(
+        HOST_MODULE,
+        "set_user_id",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> wasmtime::Result<()> {
+            if len <= 0 || len as usize > MAX_USER_ID_LEN {
+                return Err(wasmtime::Error::msg(format!("user id must be 1 to {MAX_USER_ID_LEN} bytes")));
+            }
+            let memory = memory(&mut caller)?;
+            let (memory, state) = memory.data_and_store_mut(&mut caller);
+            let user_id = memory
+                .get(guest_range(ptr, len))
+                .ok_or_else(|| wasmtime::Error::msg("set_user_id out of bounds"))?;
+            state.user_id = Some(user_id.to_vec());
+            Ok(())
+        },
+    )?;
+    Ok(linker)
+}
+
+impl WasmModule {
+    // Runs to completion or until the fuel or memory limit traps it
+    fn run(&self, state: HostState) -> Result<Verdict, Box<dyn Error + Send + Sync>> {
+        let mut store = Store::new(self.instance.module().engine(), state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.fuel)?;
+        let instance = self.instance
+            .instantiate(&mut store)
+            .map_err(|e| format!("{}: instantiation failed: {e}", self.name))?;
+        let check = instance.get_typed_func::<(), i32>(&mut store, "check")?;
+        let result = check
+            .call(&mut store, ())
+            .map_err(|e| format!("{}: {e}", self.name))?;
+        match result {
+            1 => Ok(Verdict::Passed(store.into_data().user_id)),
+            0 => Ok(Verdict::Rejected),
+            other => Err(format!("{}: check returned {other}", self.name).into()),
+        }
+    }
+}
+
+impl CustomHandler for WasmHandler {
+    fn execute<'a>(&'a self, ctx: HandlerContext<'a>) -> HandlerFuture<'a> {
+        let module = self.0.clone();
+        let state = HostState {
+            data: ctx.data.to_string(),
+            params: ctx.params.to_string(),
+            windows: ctx.verification.received.windows().map(str::to_string).collect(),
+            user_id: None,
+            limits: StoreLimitsBuilder::new()
+                .memory_size(module.max_memory)
+                .table_elements(MAX_TABLE_ELEMENTS)
+                .instances(1)
+                .memories(1)
+                .tables(1)
+                .trap_on_grow_failure(true)
+                .build(),
+        };
+        Box::pin(async move {
+            let name = module.name.clone();
+            // Bounded by fuel, but still too long to run on the async runtime
+            match tokio::task::spawn_blocking(move || module.run(state)).await?? {
+                Verdict::Passed(Some(user_id)) => Ok(HandlerOutcome::Identified(user_id)),
+                Verdict::Passed(None) => Ok(HandlerOutcome::Passed),
+                Verdict::Rejected => Ok(HandlerOutcome::Rejected(format!("rejected by {name}"))),
+            }
+        })
+    }
+}
+
+pub fn wasm_handlers_path() -> Option<String> {
+    std::env::var("WASM_HANDLERS_FILE").ok().filter(|path| !path.is_empty())
+}
+
+// Compiles every module of WASM_HANDLERS_FILE. Imports are resolved here,
+// so a module asking for anything outside the host API fails to load.
+pub fn load_wasm_handlers(filename: &str) -> Result<Handlers, Box<dyn Error>> {
+    let data = std::fs::read_to_string(filename)
+        .map_err(|e| format!("failed to read {filename}: {e}"))?;
+    let entries: HashMap<String, WasmHandlerEntry> = serde_json::from_str(&data)
+        .map_err(|e| format!("failed to parse {filename}: {e}"))?;
+
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let linker = linker(&engine)?;
+
+    let mut handlers = Handlers::new();
+    for (name, entry) in entries {
+        let module = Module::from_file(&engine, &entry.path)
+            .map_err(|e| format!("failed to compile {name} from {}: {e}", entry.path))?;
+        let instance = linker
+            .instantiate_pre(&module)
+            .map_err(|e| format!("failed to link {name}: {e}"))?;
+        let handler = WasmHandler(Arc::new(WasmModule {
+            name: name.clone(),
+            instance,
+            fuel: entry.fuel,
+            max_memory: entry.max_memory,
+        }));
+        handlers.insert(name, Arc::new(handler));
+    }
+    info!("loaded {} WASM handler(s) from {filename}", handlers.len());
+    Ok(handlers)
+}
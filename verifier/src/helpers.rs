@@ -6,10 +6,11 @@ mod oauth_signer;
 
 pub use credential_id::credential_id;
 pub use credential_id::random_credential_id;
-pub use credential_id::credential_id_from_bytes;
+pub use credential_id::{credential_id_from_bytes, credential_tag, FamilySources, OAUTH_SOURCE, TLSN_SOURCE};
+pub use credential_id::{Credential, VersionedCredentialId};
 pub use error::{ApiError, ErrorCode};
 pub use registry::registry_from_string;
-pub use registry::is_registry_whitelisted;
-pub use verifier_response::verifier_response;
+pub use registry::load_registry_whitelist;
+pub use verifier_response::{attestation_target, verifier_response};
 pub use verifier_response::VerifyResponse;
-pub use oauth_signer::get_oauth_signer;
+pub use oauth_signer::{load_oauth_signers, oauth_signers_path, OAuthSigner, OAuthSigners};
@@ -1,81 +1,284 @@
+use std::collections::HashMap;
 use std::error::Error;
 use alloy::primitives::{keccak256, B256, U256};
+use hmac::{Hmac, Mac};
 use rand::{rng, RngCore};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::Sha256;
 use tracing::{error, instrument, warn};
-use crate::config;
-use crate::core::PresentationCheck;
-use crate::services::HandlersManager;
-
-pub fn random_credential_id() -> B256 {
-    let mut random_bytes = [0u8; 32];
-    rng().fill_bytes(&mut random_bytes);
-    B256::from(random_bytes)
+use crate::config::{self, CredentialIdScheme, CredentialIdSecret};
+use crate::core::{CheckContext, PresentationCheck};
+use crate::services::{HandlerOutcome, HandlersManager};
+
+const HMAC_DOMAIN: &[u8] = b"bringid/credential-id";
+
+pub const TLSN_SOURCE: &str = "tlsn";
+pub const OAUTH_SOURCE: &str = "oauth";
+
+// Domain separation tag of a credential, so that the same id string from
+// two providers never gives the same credential id. Groups of a family
+// share it and so the credential id; standalone ones are tagged with where
+// the user id comes from (the TLSN host or the OAuth domain). The family
+// tag has no source in it, which is why a family must keep to one source.
+pub fn credential_tag(family: u32, source: &str, name: &str) -> String {
+    match family {
+        0 => source_tag(source, name),
+        family => format!("family:{family}"),
+    }
+}
+
+fn source_tag(source: &str, name: &str) -> String {
+    format!("{source}:{name}")
+}
+
+// Source of every family in use, across the TLSN and OAuth configs
+#[derive(Debug, Clone, Default)]
+pub struct FamilySources(HashMap<u32, (String, String)>);
+
+impl FamilySources {
+    pub fn add(&mut self, group: &str, family: u32, source: &str, name: &str) -> Result<(), String> {
+        if family == 0 {
+            return Ok(());
+        }
+        let tag = source_tag(source, name);
+        match self.0.get(&family) {
+            Some((first, expected)) if *expected != tag => {
+                Err(format!("group {group}: family {family} is {expected} in group {first}, not {tag}"))
+            },
+            Some(_) => Ok(()),
+            None => {
+                self.0.insert(family, (group.to_string(), tag));
+                Ok(())
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct VersionedCredentialId {
+    pub version: u32,
+    pub credential_id: B256,
 }
 
+// The credential id of one user under every configured secret version,
+// so that the request can pick the one its registration was made with
+#[derive(Debug, Clone)]
+pub struct CredentialIds(Vec<VersionedCredentialId>);
+
+impl CredentialIds {
+    pub fn get(&self, version: u32) -> Option<B256> {
+        self.0
+            .iter()
+            .find(|id| id.version == version)
+            .map(|id| id.credential_id)
+    }
+
+    pub fn all(&self) -> &[VersionedCredentialId] {
+        &self.0
+    }
+}
+
+// What a verified proof identified: the user's credential ids
+// and the family of the group they were derived for
+#[derive(Debug, Clone)]
+pub struct Credential {
+    pub family: u32,
+    pub ids: CredentialIds,
+}
+
+pub fn random_credential_id() -> CredentialIds {
+    CredentialIds(
+        config::get().credential_id_secrets.secrets
+            .iter()
+            .map(|secret| {
+                let mut random_bytes = [0u8; 32];
+                rng().fill_bytes(&mut random_bytes);
+                VersionedCredentialId {
+                    version: secret.version,
+                    credential_id: B256::from(random_bytes),
+                }
+            })
+            .collect()
+    )
+}
+
+fn derive_credential_id(
+    secret: &CredentialIdSecret,
+    tag: &str,
+    user_id_bytes: &[u8],
+    app_id: &U256,
+) -> Result<B256, Box<dyn Error + Send + Sync>> {
+    match secret.scheme {
+        // Untagged on purpose, see `CredentialIdScheme::Keccak`
+        CredentialIdScheme::Keccak => {
+            let mut buf = Vec::new();
+            buf.extend_from_slice(user_id_bytes);
+            buf.extend_from_slice(&app_id.to_be_bytes::<32>());
+            buf.extend_from_slice(&secret.secret);
+            Ok(keccak256(&buf))
+        },
+        CredentialIdScheme::HmacSha256 => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(&secret.secret)?;
+            mac.update(HMAC_DOMAIN);
+            mac.update(&(tag.len() as u32).to_be_bytes());
+            mac.update(tag.as_bytes());
+            mac.update(&app_id.to_be_bytes::<32>());
+            mac.update(user_id_bytes);
+            Ok(B256::from_slice(&mac.finalize().into_bytes()))
+        },
+    }
+}
+
+// The only place credential ids are derived: handlers and OAuth
+// messages provide the user id, never the id itself
 pub fn credential_id_from_bytes(
+    tag: &str,
+    user_id_bytes: &[u8],
+    app_id: &U256,
+) -> Result<CredentialIds, Box<dyn Error + Send + Sync>> {
+    credential_ids(tag, user_id_bytes, user_id_bytes, app_id)
+}
+
+// `legacy_bytes` is what Keccak secrets are given instead, so that
+// the ids registered before the JSON selection stay the same
+fn credential_ids(
+    tag: &str,
     user_id_bytes: &[u8],
+    legacy_bytes: &[u8],
     app_id: &U256,
-) -> Result<B256, Box<dyn Error>> {
-    let private_key_bytes = config::get().private_key.to_bytes();
-    let mut buf = Vec::new();
-    buf.extend_from_slice(user_id_bytes);
-    buf.extend_from_slice(&app_id.to_be_bytes::<32>());
-    buf.extend_from_slice(&private_key_bytes);
-    Ok(keccak256(&buf))
+) -> Result<CredentialIds, Box<dyn Error + Send + Sync>> {
+    config::get().credential_id_secrets.secrets
+        .iter()
+        .map(|secret| {
+            let user_id_bytes = match secret.scheme {
+                CredentialIdScheme::Keccak => legacy_bytes,
+                CredentialIdScheme::HmacSha256 => user_id_bytes,
+            };
+            Ok(VersionedCredentialId {
+                version: secret.version,
+                credential_id: derive_credential_id(secret, tag, user_id_bytes, app_id)?,
+            })
+        })
+        .collect::<Result<_, Box<dyn Error + Send + Sync>>>()
+        .map(CredentialIds)
+}
+
+fn tlsn_credential_id(
+    ctx: &CheckContext<'_>,
+    user_id_bytes: &[u8],
+    legacy_bytes: &[u8],
+) -> Result<CredentialIds, Box<dyn Error>> {
+    let tag = credential_tag(ctx.family, TLSN_SOURCE, ctx.server_name);
+    credential_ids(&tag, user_id_bytes, legacy_bytes, ctx.app_id).map_err(|e| e as Box<dyn Error>)
+}
+
+// How the user id was read from a window before JSON selection: the
+// text after its first `:`, trimmed and unquoted. Differs from the
+// selected value for numbers and for windows with more than one member.
+fn legacy_user_id(data: &str) -> Option<&str> {
+    Some(data.split(":").nth(1)?.trim().trim_matches('"'))
 }
 
 #[instrument(
     name="credential_id",
     level="info",
-    skip(transcript_authed, check),
+    skip(ctx, check),
     err
 )]
 pub async fn credential_id(
-    transcript_authed: &[String],
+    ctx: &CheckContext<'_>,
     check: &PresentationCheck,
-    app_id: &U256,
-) -> Result<B256, Box<dyn Error>> {
+) -> Result<CredentialIds, Box<dyn Error>> {
     match std::env::var("ENV") {
         Ok(env) if env == "dev"  => {
             Ok(random_credential_id())
         }
         _ => {
             if check.custom_handler.is_some() {
-                let (success, credential_id) = HandlersManager::execute(
+                let outcome = HandlersManager::execute(
                     check,
-                    transcript_authed
-                        .get(check.window.id)
+                    check.window
+                        .locate(ctx.received)
                         .ok_or_else(|| {
                             warn!("user ID window is not found");
                             "user ID window is not found"
                         })?,
-                    app_id
-                ).await.inspect_err(|_| warn!("verification handler execution failed"))?;
-                if !success {
-                    warn!("user ID check was unsuccessful");
-                    return Err("User ID check was unsuccessful".into());
+                    ctx
+                ).await.map_err(|e| {
+                    // Only logged, it may quote the revealed data
+                    warn!("verification handler execution failed: {e}");
+                    "user ID handler failed"
+                })?;
+                match outcome {
+                    HandlerOutcome::Identified(user_id) => tlsn_credential_id(ctx, &user_id, &user_id),
+                    HandlerOutcome::Passed => {
+                        error!("user ID was not provided by the handler");
+                        Err("user ID was not provided by the handler".into())
+                    },
+                    HandlerOutcome::Rejected(reason) => {
+                        warn!("user ID check was unsuccessful");
+                        Err(format!("User ID check was unsuccessful: {reason}").into())
+                    },
                 }
-                Ok(credential_id.ok_or_else(|| {
-                    error!("credential ID was not computed");
-                    "credential ID was not computed"
-                })?)
             } else {
-                let id_bytes = transcript_authed.get(check.window.id)
+                let data = check.window.locate(ctx.received)
                     .ok_or_else(|| {
                         warn!("user ID window is not found");
                         "user ID window is not found"
-                    })?
-                    .split(":")
-                    .nth(1)
-                    .ok_or_else(|| {
-                        warn!("user ID is not presented");
-                        "user ID is not presented"
-                    })?
-                    .trim()
-                    .trim_matches('"')
-                    .as_bytes();
-                credential_id_from_bytes(id_bytes, app_id)
+                    })?;
+                let user_id = check.window.select(data).ok_or_else(|| {
+                    warn!("user ID is not presented");
+                    "user ID is not presented"
+                })?;
+                let id_bytes = match &user_id {
+                    Value::String(user_id) => user_id.clone(),
+                    user_id => user_id.to_string(),
+                };
+                let legacy_bytes = legacy_user_id(data).unwrap_or(&id_bytes);
+                tlsn_credential_id(ctx, id_bytes.as_bytes(), legacy_bytes.as_bytes())
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derive(scheme: CredentialIdScheme, tag: &str) -> B256 {
+        let secret = CredentialIdSecret { version: 0, scheme, secret: vec![0x11; 32] };
+        derive_credential_id(&secret, tag, b"12345", &U256::from(1)).unwrap()
+    }
+
+    // keccak256("12345" || app_id || secret), the baseline formula
+    #[test]
+    fn keccak_matches_the_original_formula() {
+        let id = derive(CredentialIdScheme::Keccak, "family:2");
+        assert_eq!(id, "0xc83c832aa12ec4796054b06198212d3fe45d1d946cdaacae1b19bdec5cd507e8".parse::<B256>().unwrap());
+        assert_eq!(id, derive(CredentialIdScheme::Keccak, "tlsn:uber.com"));
+    }
+
+    // HMAC-SHA256(secret, "bringid/credential-id" || u32be(8) || "family:2" || app_id || "12345")
+    #[test]
+    fn hmac_layout() {
+        let id = derive(CredentialIdScheme::HmacSha256, "family:2");
+        assert_eq!(id, "0x7086c03c6f8b1428163961f3798d3390893f892415319e99130742a913fa1341".parse::<B256>().unwrap());
+        assert_ne!(id, derive(CredentialIdScheme::HmacSha256, "family:3"));
+    }
+
+    // Byte for byte what the baseline read, including its quirks
+    #[test]
+    fn legacy_user_id_is_the_text_after_the_first_colon() {
+        assert_eq!(legacy_user_id(r#""uuid":"4f2c-91ab""#), Some("4f2c-91ab"));
+        assert_eq!(legacy_user_id(r#""userId": 12345,"#), Some("12345,"));
+        assert_eq!(legacy_user_id(r#""uuid":"a","name":"b""#), Some(r#"a","name"#));
+        assert_eq!(legacy_user_id("12345"), None);
+    }
+
+    #[test]
+    fn family_tag_ignores_the_source() {
+        assert_eq!(credential_tag(2, TLSN_SOURCE, "github.com"), "family:2");
+        assert_eq!(credential_tag(0, OAUTH_SOURCE, "github.com"), "oauth:github.com");
+    }
+}
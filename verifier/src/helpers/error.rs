@@ -4,6 +4,7 @@ use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Serialize;
 use std::fmt;
+use crate::core::CheckFailure;
 
 #[derive(Debug, Clone, Copy, Serialize)]
 pub enum ErrorCode {
@@ -13,6 +14,8 @@ pub enum ErrorCode {
     PresentationDeserializeFailed,
     #[serde(rename = "PROOF_VERIFICATION_FAILED")]
     ProofVerificationFailed,
+    #[serde(rename = "PRESENTATION_EXPIRED")]
+    PresentationExpired,
     #[serde(rename = "INVALID_SEMAPHORE_COMMITMENT")]
     InvalidSemaphoreCommitment,
     #[serde(rename = "SIGNATURE_PARSE_FAILED")]
@@ -29,6 +32,14 @@ pub enum ErrorCode {
     VerificationNotFound,
     #[serde(rename = "VERIFICATION_CHECK_FAILED")]
     VerificationCheckFailed,
+    #[serde(rename = "OAUTH_MESSAGE_EXPIRED")]
+    OauthMessageExpired,
+    #[serde(rename = "OAUTH_MESSAGE_FUTURE_DATED")]
+    OauthMessageFutureDated,
+    #[serde(rename = "OAUTH_MESSAGE_BINDING_MISMATCH")]
+    OauthMessageBindingMismatch,
+    #[serde(rename = "LEGACY_OAUTH_MESSAGE_REJECTED")]
+    LegacyOauthMessageRejected,
     #[serde(rename = "INVALID_REGISTRY_ADDRESS")]
     InvalidRegistryAddress,
     #[serde(rename = "REGISTRY_NOT_WHITELISTED")]
@@ -41,6 +52,14 @@ pub enum ErrorCode {
     SigningFailed,
     #[serde(rename = "INVALID_REQUEST_BODY")]
     InvalidRequestBody,
+    #[serde(rename = "REPLAY_DETECTED")]
+    ReplayDetected,
+    #[serde(rename = "REPLAY_CHECK_FAILED")]
+    ReplayCheckFailed,
+    #[serde(rename = "ADMIN_UNAUTHORIZED")]
+    AdminUnauthorized,
+    #[serde(rename = "CONFIG_RELOAD_FAILED")]
+    ConfigReloadFailed,
 }
 
 impl From<JsonRejection> for ApiError {
@@ -54,6 +73,9 @@ pub struct ErrorBody {
     pub success: bool,
     pub errors: Vec<ErrorCode>,
     pub message: String,
+    // Which checks failed, when a verification was rejected
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<CheckFailure>,
 }
 
 #[derive(Debug)]
@@ -70,10 +92,16 @@ impl ApiError {
                 success: false,
                 errors: vec![code],
                 message: message.to_string(),
+                details: Vec::new(),
             },
         }
     }
 
+    pub fn with_details(mut self, details: Vec<CheckFailure>) -> Self {
+        self.body.details = details;
+        self
+    }
+
     pub fn bad_request(code: ErrorCode, message: impl fmt::Display) -> Self {
         Self::new(StatusCode::BAD_REQUEST, code, message)
     }
@@ -82,6 +110,10 @@ impl ApiError {
         Self::new(StatusCode::UNAUTHORIZED, code, message)
     }
 
+    pub fn conflict(code: ErrorCode, message: impl fmt::Display) -> Self {
+        Self::new(StatusCode::CONFLICT, code, message)
+    }
+
     pub fn internal(code: ErrorCode, message: impl fmt::Display) -> Self {
         Self::new(StatusCode::INTERNAL_SERVER_ERROR, code, message)
     }
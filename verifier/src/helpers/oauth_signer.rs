@@ -1,31 +1,90 @@
 use alloy::primitives::Address;
-use once_cell::sync::Lazy;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::error::Error;
 use std::str::FromStr;
+use std::sync::Arc;
 use tracing::info;
 
-static OAUTH_SIGNERS: Lazy<HashMap<String, Address>> = Lazy::new(|| {
-    let filename = if matches!(std::env::var("ENV"), Ok(ref v) if v == "dev") {
+#[derive(Clone, Debug, Deserialize)]
+pub struct OAuthSigner {
+    pub address: Address,
+    #[serde(default)]
+    pub not_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+impl OAuthSigner {
+    pub fn is_valid_at(&self, time: DateTime<Utc>) -> bool {
+        self.not_before.is_none_or(|not_before| time >= not_before)
+            && self.not_after.is_none_or(|not_after| time <= not_after)
+    }
+}
+
+// A group maps either to a single address, or to a list of signers
+// with optional validity windows so keys can be rotated with an overlap
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SignersEntry {
+    Single(String),
+    List(Vec<SignerEntry>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SignerEntry {
+    Address(String),
+    Signer(OAuthSigner),
+}
+
+fn parse_address(credential_group_id: &str, address: &str) -> Result<Address, String> {
+    Address::from_str(address)
+        .map_err(|_| format!("invalid address for credential_group_id {credential_group_id}: {address}"))
+}
+
+pub fn oauth_signers_path() -> &'static str {
+    if matches!(std::env::var("ENV"), Ok(ref v) if v == "dev") {
         "oauth_signers_staging.json"
     } else {
         "oauth_signers.json"
-    };
+    }
+}
+
+pub type OAuthSigners = HashMap<String, Arc<[OAuthSigner]>>;
+
+pub fn load_oauth_signers(filename: &str) -> Result<OAuthSigners, Box<dyn Error>> {
     let data = std::fs::read_to_string(filename)
-        .unwrap_or_else(|_| panic!("failed to read {filename}"));
-    let raw: HashMap<String, String> =
-        serde_json::from_str(&data).unwrap_or_else(|_| panic!("failed to parse {filename}"));
-    let signers: HashMap<String, Address> = raw
-        .into_iter()
-        .map(|(k, v)| {
-            let addr = Address::from_str(&v)
-                .unwrap_or_else(|_| panic!("invalid address for credential_group_id {k}: {v}"));
-            (k, addr)
-        })
-        .collect();
-    info!("loaded {} OAuth signer(s) from {filename}", signers.len());
-    signers
-});
-
-pub fn get_oauth_signer(credential_group_id: &str) -> Option<&Address> {
-    OAUTH_SIGNERS.get(credential_group_id)
+        .map_err(|e| format!("failed to read {filename}: {e}"))?;
+    let raw: HashMap<String, SignersEntry> = serde_json::from_str(&data)
+        .map_err(|e| format!("failed to parse {filename}: {e}"))?;
+    let mut signers = OAuthSigners::new();
+    for (k, v) in raw {
+        let group_signers = match v {
+            SignersEntry::Single(address) => vec![OAuthSigner {
+                address: parse_address(&k, &address)?,
+                not_before: None,
+                not_after: None,
+            }],
+            SignersEntry::List(entries) => entries
+                .into_iter()
+                .map(|entry| match entry {
+                    SignerEntry::Address(address) => Ok(OAuthSigner {
+                        address: parse_address(&k, &address)?,
+                        not_before: None,
+                        not_after: None,
+                    }),
+                    SignerEntry::Signer(signer) => Ok(signer),
+                })
+                .collect::<Result<_, String>>()?,
+        };
+        signers.insert(k, group_signers.into());
+    }
+    info!(
+        "loaded {} OAuth signer(s) for {} credential group(s) from {filename}",
+        signers.values().map(|signers| signers.len()).sum::<usize>(),
+        signers.len()
+    );
+    Ok(signers)
 }
@@ -1,22 +1,23 @@
+use std::error::Error;
 use std::str::FromStr;
 use alloy::hex::FromHexError;
 use alloy::primitives::Address;
-use once_cell::sync::Lazy;
 
-pub static REGISTRY_WHITELIST: Lazy<Vec<Address>> = Lazy::new(|| {
-    std::env::var("REGISTRY_WHITELIST")
-        .expect("REGISTRY_WHITELIST not set")
+fn parse_whitelist(whitelist: &str) -> Result<Vec<Address>, FromHexError> {
+    whitelist
         .split(',')
-        .map(|s| Address::from_str(s.trim()).expect("invalid address in REGISTRY_WHITELIST"))
+        .map(|s| Address::from_str(s.trim()))
         .collect()
-});
+}
+
+pub fn load_registry_whitelist() -> Result<Vec<Address>, Box<dyn Error>> {
+    let whitelist = std::env::var("REGISTRY_WHITELIST").map_err(|_| "REGISTRY_WHITELIST not set")?;
+    parse_whitelist(&whitelist)
+        .map_err(|e| format!("invalid address in REGISTRY_WHITELIST: {e}").into())
+}
 
 pub fn registry_from_string(
     registry_address: String
 ) -> Result<Address, FromHexError> {
     Address::from_str(registry_address.as_str())
 }
-
-pub fn is_registry_whitelisted(address: &Address) -> bool {
-    REGISTRY_WHITELIST.contains(address)
-}
\ No newline at end of file
@@ -1,13 +1,14 @@
 use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 use alloy::{hex, sol};
-use alloy::primitives::{keccak256, B256, U256};
+use alloy::primitives::{keccak256, Address, U256};
 use alloy::sol_types::SolValue;
-use alloy::signers::Signer;
 use axum::Json;
 use serde::Serialize;
 use tracing::{error, info};
-use crate::helpers::{registry_from_string, is_registry_whitelisted, ApiError, ErrorCode};
+use crate::config;
+use crate::helpers::{registry_from_string, ApiError, Credential, ErrorCode, VersionedCredentialId};
+use crate::services::RuntimeConfig;
 use crate::signer;
 
 fn serialize_u256_as_string<S>(value: &U256, serializer: S) -> Result<S::Ok, S::Error>
@@ -54,24 +55,29 @@ pub struct VerifyResponse {
     attestation: Attestation,
     verifier_hash: String,
     signature: String,
+    // Credential family of the group, 0 for a standalone group
+    family: u32,
+    credential_id_version: u32,
+    // Only in migration mode, the attestation is signed for one of them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    credential_ids: Vec<VersionedCredentialId>,
 }
 
 const VALID_CHAIN_IDS: &[u64] = &[8453, 84532];
 
-pub async fn verifier_response(
-    registry_address: String,
+// Where the attestation will be used. Checked before the proof is recorded
+// in the replay cache, so a request for a wrong target doesn't use it up.
+pub fn attestation_target(
+    runtime: &RuntimeConfig,
+    registry_address: &str,
     chain_id: u64,
-    credential_group_id: String,
-    app_id: String,
-    semaphore_identity_commitment: U256,
-    credential_id: B256,
-) -> Result<Json<VerifyResponse>, ApiError> {
-    let registry = registry_from_string(registry_address)
+) -> Result<Address, ApiError> {
+    let registry = registry_from_string(registry_address.to_string())
         .map_err(|e| {
             ApiError::bad_request(ErrorCode::InvalidRegistryAddress, e)
         })?;
 
-    if !is_registry_whitelisted(&registry) {
+    if !runtime.is_registry_whitelisted(&registry) {
         error!("registry address not whitelisted: {registry}");
         return Err(ApiError::bad_request(
             ErrorCode::RegistryNotWhitelisted,
@@ -83,12 +89,33 @@ pub async fn verifier_response(
         error!("unsupported chain_id: {chain_id}");
         return Err(ApiError::bad_request(ErrorCode::UnsupportedChainId, format!("unsupported chain_id: {chain_id}")));
     }
+    Ok(registry)
+}
+
+// `registry` and `chain_id` must have passed `attestation_target`
+pub async fn verifier_response(
+    registry: Address,
+    chain_id: u64,
+    credential_group_id: String,
+    app_id: String,
+    semaphore_identity_commitment: U256,
+    credential: Credential,
+) -> Result<Json<VerifyResponse>, ApiError> {
 
     let app_id = U256::from_str(app_id.as_str()).map_err(|e| {
         error!("invalid app_id: {e}");
         ApiError::bad_request(ErrorCode::InvalidAppId, e)
     })?;
 
+    // Always the active version: signing for an older one would let the
+    // same account register again. Older ids are only listed for lookups.
+    let secrets = &config::get().credential_id_secrets;
+    let credential_id_version = secrets.active;
+    let credential_id = credential.ids.get(credential_id_version).ok_or_else(|| {
+        error!("no credential id for version {credential_id_version}");
+        ApiError::internal(ErrorCode::CredentialIdFailed, "credential id of the active version is missing")
+    })?;
+
     let issued_at = U256::from(
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -106,7 +133,7 @@ pub async fn verifier_response(
         issuedAt: issued_at,
     };
     let message = keccak256(attestation.abi_encode());
-    let signature = signer::get().sign_message(message.as_slice())
+    let signature = signer::sign_message(message.as_slice())
         .await
         .map_err(|e| {
             error!("unexpected error during message signing: {}", e);
@@ -119,6 +146,9 @@ pub async fn verifier_response(
             attestation,
             verifier_hash: hex::encode_prefixed(message),
             signature: signature.to_string(),
+            family: credential.family,
+            credential_id_version,
+            credential_ids: if secrets.migration { credential.ids.all().to_vec() } else { Vec::new() },
         })
     )
 }
@@ -0,0 +1,301 @@
+use std::error::Error;
+use std::ops::Range;
+use std::str::FromStr;
+use alloy::hex::{self, ToHexExt};
+use alloy::primitives::U256;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tlsn_core::presentation::{Presentation, PresentationOutput};
+use tlsn_core::CryptoProvider;
+use crate::config;
+use crate::core::{CheckContext, CheckExpr, PresentationCheck, Transcript, Verification};
+use crate::custom_handlers;
+use crate::services::{HandlerOutcome, Handlers, HandlersManager, VerificationManager, VERIFICATIONS_PATH};
+
+const USAGE: &str = "usage: verifier inspect <presentation.json | presentation.hex> [--group <credential_group_id>] [--app-id <uint256>]";
+const MAX_TEXT: usize = 300;
+
+struct Args {
+    input: String,
+    group: Option<String>,
+    app_id: String,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, Box<dyn Error>> {
+    let mut input = None;
+    let mut group = None;
+    let mut app_id = "0".to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--group" => group = Some(args.next().ok_or(USAGE)?.clone()),
+            "--app-id" => app_id = args.next().ok_or(USAGE)?.clone(),
+            _ if arg.starts_with('-') || input.is_some() => return Err(USAGE.into()),
+            _ => input = Some(arg.clone()),
+        }
+    }
+    Ok(Args { input: input.ok_or(USAGE)?, group, app_id })
+}
+
+// Either the JSON exported by the extension (`tlsn_presentation` field,
+// as read by scripts/verify.sh) or a file with just the hex presentation
+fn read_presentation(path: &str) -> Result<Presentation, Box<dyn Error>> {
+    let data = std::fs::read_to_string(path)?;
+    let data = data.trim();
+    let presentation = if data.starts_with('{') {
+        let json: Value = serde_json::from_str(data)?;
+        json.get("tlsn_presentation")
+            .and_then(Value::as_str)
+            .ok_or("\"tlsn_presentation\" field not found")?
+            .to_string()
+    } else {
+        data.to_string()
+    };
+    let presentation = hex::decode(presentation.trim_start_matches("0x"))
+        .map_err(|e| format!("presentation is not valid hex: {e}"))?;
+    bincode::deserialize(&presentation)
+        .map_err(|e| format!("presentation deserialization failed: {e}").into())
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_TEXT) {
+        Some((end, _)) => format!("{:?}... ({} bytes)", &text[..end], text.len()),
+        None => format!("{text:?}"),
+    }
+}
+
+fn print_ranges(title: &str, data: &[u8], ranges: impl Iterator<Item = Range<usize>>) {
+    println!("{title}:");
+    // Indexes are the ones a window `id` refers to: only UTF-8 ranges count
+    let mut index = 0;
+    for range in ranges {
+        match data.get(range.clone()).map(std::str::from_utf8) {
+            Some(Ok(text)) => {
+                println!("  #{index} {range:?} {}", truncate(text));
+                index += 1;
+            },
+            _ => println!("  -- {range:?} <not UTF-8, not addressable>"),
+        }
+    }
+}
+
+fn verdict(passed: bool) -> &'static str {
+    if passed { "pass" } else { "FAIL" }
+}
+
+// `None` when the check could not be evaluated (missing window, handler
+// error), which `verification.check` treats as an error rather than a fail
+async fn explain_check(label: &str, check: &PresentationCheck, ctx: &CheckContext<'_>) -> Option<bool> {
+    let expected = serde_json::to_string(&check.check).unwrap_or_default();
+    match &check.custom_handler {
+        Some(handler) => println!("{label}: {expected} via custom handler {handler}"),
+        None => println!("{label}: {expected}"),
+    }
+    let Some((located, data)) = check.window.locate_with_source(ctx.received) else {
+        println!("    window: not found");
+        println!("    result: FAIL (missing window data)");
+        return None;
+    };
+    println!("    window: {located}: {}", truncate(data));
+    if check.custom_handler.is_some() {
+        match HandlersManager::execute(check, data, ctx).await {
+            Ok(HandlerOutcome::Rejected(reason)) => {
+                println!("    result: FAIL ({reason})");
+                Some(false)
+            },
+            Ok(_) => {
+                println!("    result: pass");
+                Some(true)
+            },
+            Err(e) => {
+                println!("    result: FAIL (handler error: {e})");
+                None
+            },
+        }
+    } else {
+        let passed = check.check(data);
+        println!("    value: {}", truncate(&check.observed(data)));
+        println!("    result: {}", verdict(passed));
+        Some(passed)
+    }
+}
+
+// Folds the results of `expr.leaves()`, in that order, the way
+// `CheckExpr::evaluate` would, so that handlers are not run again
+fn combine(expr: &CheckExpr, results: &mut impl Iterator<Item = Option<bool>>) -> Option<bool> {
+    match expr {
+        CheckExpr::All { all } => {
+            let results: Vec<_> = all.iter().map(|expr| combine(expr, results)).collect();
+            results.into_iter().find(|result| *result != Some(true)).unwrap_or(Some(true))
+        },
+        CheckExpr::Any { any } => {
+            let results: Vec<_> = any.iter().map(|expr| combine(expr, results)).collect();
+            if results.contains(&Some(true)) {
+                Some(true)
+            } else if results.contains(&None) {
+                None
+            } else {
+                Some(false)
+            }
+        },
+        CheckExpr::Not { not } => combine(not, results).map(|passed| !passed),
+        CheckExpr::Check(_) => results.next().flatten(),
+    }
+}
+
+async fn dry_run(
+    verification: &Verification,
+    output: &PresentationOutput,
+    notary_id: Option<&str>,
+    sent: &Transcript,
+    received: &Transcript,
+    app_id: &U256,
+    handlers: &Handlers,
+) {
+    let server_name = output.server_name.as_ref().map(|name| name.to_string()).unwrap_or_default();
+    let mut passed = verification.host == server_name;
+    println!("host: expected {}, got {server_name}: {}", verification.host, verdict(passed));
+    let ctx = CheckContext {
+        server_name: &server_name,
+        credential_group_id: &verification.id,
+        family: verification.family,
+        app_id,
+        sent,
+        received,
+        handlers,
+    };
+    match notary_id {
+        Some(id) => println!("notary: {id}: {}", verdict(verification.accepts_notary(id))),
+        None => println!("notary: not checked"),
+    }
+    match verification.check_freshness(output.connection_info.time) {
+        Ok(()) => println!("freshness: pass"),
+        Err(e) => println!("freshness: FAIL ({e})"),
+    }
+    if let Some(request) = &verification.request {
+        match request.check(sent) {
+            Ok(()) => println!("request: pass"),
+            Err(e) => {
+                println!("request: FAIL ({e})");
+                passed = false;
+            },
+        }
+    }
+    if let Some(response) = &verification.response {
+        match response.check(received) {
+            Ok(()) => println!("response: pass"),
+            Err(e) => {
+                println!("response: FAIL ({e})");
+                passed = false;
+            },
+        }
+    }
+
+    passed &= explain_check("user_id", &verification.user_id, &ctx).await == Some(true);
+    for (index, expr) in verification.checks.iter().enumerate() {
+        let mut results = Vec::new();
+        for check in expr.leaves() {
+            results.push(explain_check(&format!("checks[{index}]"), check, &ctx).await);
+        }
+        let result = combine(expr, &mut results.into_iter());
+        if !matches!(expr, CheckExpr::Check(_)) {
+            match result {
+                Some(result) => println!("checks[{index}]: expression: {}", verdict(result)),
+                None => println!("checks[{index}]: expression: FAIL (a check could not be evaluated)"),
+            }
+        }
+        passed &= result == Some(true);
+    }
+
+    println!("\nverification: {}", verdict(passed));
+}
+
+// `verifier inspect`: prints what a presentation reveals and, with
+// `--group`, runs that group's checks on it. Nothing is signed.
+pub async fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let args = parse_args(args)?;
+    let app_id = U256::from_str(&args.app_id).map_err(|e| format!("invalid app_id: {e}"))?;
+
+    let output = read_presentation(&args.input)?
+        .verify(&CryptoProvider::default())
+        .map_err(|e| format!("presentation verification failed: {e}"))?;
+    let transcript = output.transcript.as_ref().ok_or("Transcript is not provided")?;
+
+    let time = output.connection_info.time;
+    println!("server name: {}", output.server_name.as_ref().map(|name| name.to_string()).unwrap_or_default());
+    println!(
+        "connection time: {time} ({})",
+        DateTime::from_timestamp(time as i64, 0).map(|time| time.to_rfc3339()).unwrap_or_default()
+    );
+    let notary_key = output.attestation.body.verifying_key();
+    println!("notary key: {} {}", notary_key.alg, notary_key.data.encode_hex_with_prefix());
+
+    config::load_env();
+    let notary_id = if std::env::var("NOTARY_KEYS_FILE").is_ok() || std::env::var("NOTARY_KEY_HEX").is_ok() {
+        let notary = config::notaries()
+            .into_iter()
+            .find(|notary| &notary.key == notary_key && notary.is_valid_at(time, Utc::now()));
+        match &notary {
+            Some(notary) => println!("trusted notary: {}", notary.id),
+            None => println!("trusted notary: none, the key is not trusted now or at connection time"),
+        }
+        notary.map(|notary| notary.id)
+    } else {
+        println!("trusted notary: not checked, no notary keys configured");
+        None
+    };
+
+    println!();
+    print_ranges("sent authed ranges", transcript.sent_unsafe(), transcript.sent_authed().iter_ranges());
+    print_ranges("received authed ranges", transcript.received_unsafe(), transcript.received_authed().iter_ranges());
+
+    let sent = Transcript::new(transcript.sent_unsafe(), transcript.sent_authed().iter_ranges());
+    let received = Transcript::new(transcript.received_unsafe(), transcript.received_authed().iter_ranges());
+    if let Some(http) = received.http() {
+        println!(
+            "\nresponse: {} ({} revealed headers, body {})",
+            http.start_line.as_deref().unwrap_or("<status line redacted>"),
+            http.headers.len(),
+            if http.body.is_some() { "decoded" } else { "not fully revealed" }
+        );
+    }
+
+    let Some(group) = args.group else {
+        return Ok(());
+    };
+    let handlers = custom_handlers::load_all()?;
+    let verifications = VerificationManager::load_with(VERIFICATIONS_PATH, &handlers)?;
+    let verification = verifications
+        .get(&group)
+        .ok_or_else(|| format!("credential group {group} is not found in {VERIFICATIONS_PATH}"))?;
+
+    println!("\ndry run of credential group {group}:");
+    dry_run(verification, &output, notary_id.as_deref(), &sent, &received, &app_id, &handlers).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn combined(expr: Value, results: &[Option<bool>]) -> Option<bool> {
+        let expr: CheckExpr = serde_json::from_value(expr).unwrap();
+        assert_eq!(expr.leaves().len(), results.len());
+        combine(&expr, &mut results.iter().copied())
+    }
+
+    #[test]
+    fn combines_leaf_results_like_evaluate() {
+        let leaf = json!({"window": {"id": 0}, "type": "any"});
+        let any = json!({"any": [leaf, {"not": leaf}]});
+        assert_eq!(combined(any.clone(), &[None, Some(false)]), Some(true));
+        assert_eq!(combined(any.clone(), &[None, Some(true)]), None);
+        assert_eq!(combined(any, &[Some(false), Some(true)]), Some(false));
+
+        let all = json!({"all": [leaf, leaf]});
+        assert_eq!(combined(all.clone(), &[Some(false), None]), Some(false));
+        assert_eq!(combined(all.clone(), &[None, Some(false)]), None);
+        assert_eq!(combined(all, &[Some(true), Some(true)]), Some(true));
+    }
+}
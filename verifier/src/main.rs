@@ -6,38 +6,26 @@ mod services;
 mod helpers;
 mod custom_handlers;
 mod telemetry;
+mod check_config;
+mod inspect;
 use std::error::Error;
 use tracing::info;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    config::init();
-    telemetry::init_logging();
-    services::HandlersManager::register(
-        "apple_devices_user_id".to_string(),
-        custom_handlers::apple_devices_user_id
-    ).await?;
-    services::HandlersManager::register(
-        "uber_rides_amount".to_string(),
-        custom_handlers::uber_rides_amount
-    ).await?;
-    services::HandlersManager::register(
-        "apple_subs_user_id".to_string(),
-        custom_handlers::apple_subs_user_id
-    ).await?;
-
-    let oauth_verification_conf: &str;
-    match std::env::var("ENV") {
-        Ok(env) if env == "dev" => {
-            oauth_verification_conf = "oauth_verifications_staging.json"
-        }
-        _ => {
-            oauth_verification_conf = "oauth_verifications.json"
-        }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("check-config") => return check_config::run(),
+        Some("inspect") => return inspect::run(&args[1..]).await,
+        Some("mock-signer") => return signer::mock_server::run().await,
+        _ => {},
     }
 
-    services::VerificationManager::from_file("verifications.json").await?;
-    services::OAuthVerificationManager::from_file(oauth_verification_conf).await?;
+    config::init();
+    telemetry::init_logging();
+    signer::init()?;
+    services::RuntimeConfig::init()?;
+    services::ReplayCache::init()?;
 
     info!("service is running");
     services::Server::run().await
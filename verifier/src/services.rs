@@ -1,8 +1,14 @@
 mod server;
 mod verification_manager;
 mod handlers_manager;
+mod replay_cache;
+mod config_reloader;
+mod runtime_config;
 
 pub use verification_manager::worker::VerificationManager;
 pub use verification_manager::oauth_worker::OAuthVerificationManager;
-pub use handlers_manager::HandlersManager;
+pub use handlers_manager::{parse_params, CustomHandler, HandlerContext, HandlerFuture, HandlerOutcome, Handlers, HandlersManager};
+pub use replay_cache::{ReplayCache, ReplayStatus};
+pub use config_reloader::{ConfigReloader, ReloadSummary, VERIFICATIONS_PATH};
+pub use runtime_config::RuntimeConfig;
 pub use server::worker as Server;
\ No newline at end of file
@@ -0,0 +1,47 @@
+use std::error::Error;
+use serde::Serialize;
+use tracing::{info, instrument};
+use super::RuntimeConfig;
+
+pub const VERIFICATIONS_PATH: &str = "verifications.json";
+
+#[derive(Debug, Serialize)]
+pub struct ReloadSummary {
+    pub custom_handlers: usize,
+    pub verifications: usize,
+    pub oauth_verifications: usize,
+    pub oauth_signer_groups: usize,
+}
+
+pub struct ConfigReloader;
+
+impl ConfigReloader {
+    pub fn oauth_verifications_path() -> &'static str {
+        match std::env::var("ENV") {
+            Ok(env) if env == "dev" => "oauth_verifications_staging.json",
+            _ => "oauth_verifications.json",
+        }
+    }
+
+    // Every file is parsed and validated before anything is swapped in,
+    // so a broken config leaves the running one untouched. Requests that
+    // are already in flight keep the `RuntimeConfig` they started with.
+    #[instrument(
+        name="config_reload",
+        level="info",
+        err
+    )]
+    pub fn reload() -> Result<ReloadSummary, Box<dyn Error>> {
+        let config = RuntimeConfig::load(RuntimeConfig::current().registries.clone())?;
+        let summary = ReloadSummary {
+            custom_handlers: config.handlers.len(),
+            verifications: config.verifications.len(),
+            oauth_verifications: config.oauth_verifications.len(),
+            oauth_signer_groups: config.oauth_signers.len(),
+        };
+        RuntimeConfig::replace(config);
+
+        info!("config reloaded: {:?}", summary);
+        Ok(summary)
+    }
+}
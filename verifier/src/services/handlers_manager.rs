@@ -1,3 +1,5 @@
 pub mod manager;
+pub mod handler;
 
-pub use manager::HandlersManager;
\ No newline at end of file
+pub use manager::{Handlers, HandlersManager};
+pub use handler::{parse_params, CustomHandler, HandlerContext, HandlerFuture, HandlerOutcome};
@@ -0,0 +1,45 @@
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use crate::core::CheckContext;
+
+pub type HandlerFuture<'a> = Pin<Box<dyn Future<Output = Result<HandlerOutcome, Box<dyn Error + Send + Sync>>> + Send + 'a>>;
+
+// An `Err` from a handler means it could not decide (malformed data,
+// unreachable service); a check that simply doesn't pass is `Rejected`
+#[derive(Debug)]
+pub enum HandlerOutcome {
+    Passed,
+    // Passed and identified the user, what a `user_id` handler returns.
+    // Only the user id: the credential id is derived from it by the core.
+    Identified(Vec<u8>),
+    Rejected(String),
+}
+
+pub struct HandlerContext<'a> {
+    // Data of the check's own window
+    pub data: &'a str,
+    // `handler_params` of the check in verifications.json
+    pub params: &'a Value,
+    pub verification: &'a CheckContext<'a>,
+}
+
+pub trait CustomHandler: Send + Sync {
+    fn execute<'a>(&'a self, ctx: HandlerContext<'a>) -> HandlerFuture<'a>;
+
+    // Runs when a config is loaded, so that bad params
+    // fail the load rather than every request
+    fn validate_params(&self, _params: &Value) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// Handler params with the handler's defaults when the check has none
+pub fn parse_params<T: DeserializeOwned + Default>(params: &Value) -> Result<T, String> {
+    if params.is_null() {
+        return Ok(T::default());
+    }
+    T::deserialize(params).map_err(|e| format!("invalid handler_params: {e}"))
+}
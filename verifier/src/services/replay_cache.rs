@@ -0,0 +1,6 @@
+pub mod manager;
+pub mod store;
+mod memory_store;
+mod file_store;
+
+pub use manager::{ReplayCache, ReplayStatus};
\ No newline at end of file
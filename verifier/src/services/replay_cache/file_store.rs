@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Mutex;
+use alloy::primitives::B256;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use super::memory_store::MemoryStore;
+use super::store::{Binding, ReplayStore};
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    key: B256,
+    binding: Binding,
+    expires_at: u64,
+}
+
+// Append-only JSON lines file, replayed into memory on startup
+// so that entries survive restarts. It is rewritten without the
+// expired and unreadable entries each time it is opened.
+pub struct FileStore {
+    memory: MemoryStore,
+    file: Mutex<File>,
+}
+
+impl FileStore {
+    pub fn open(path: &str, now: u64) -> Result<Self, Box<dyn Error>> {
+        let memory = MemoryStore::default();
+        let mut live = Vec::new();
+        if let Ok(file) = File::open(path) {
+            for (number, line) in BufReader::new(file).lines().enumerate() {
+                // A line cut short by a crash mid-write is not worth refusing to start over
+                let entry: Entry = match serde_json::from_str(&line?) {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        warn!("skipping malformed replay cache entry at {path}:{}: {e}", number + 1);
+                        continue;
+                    },
+                };
+                if entry.expires_at > now
+                    && memory.check_and_insert(entry.key, entry.binding, now, entry.expires_at)
+                        .map_err(|e| e.to_string())?
+                        .is_none()
+                {
+                    live.push(entry);
+                }
+            }
+        }
+        info!("loaded {} replay cache entries from {path}", live.len());
+
+        Self::compact(path, &live)?;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { memory, file: Mutex::new(file) })
+    }
+
+    // Written next to the file and renamed over it, so a crash
+    // leaves either the old or the new file, never half of one
+    fn compact(path: &str, entries: &[Entry]) -> Result<(), Box<dyn Error>> {
+        let tmp_path = format!("{path}.tmp");
+        let mut tmp = File::create(&tmp_path)?;
+        for entry in entries {
+            writeln!(tmp, "{}", serde_json::to_string(entry)?)?;
+        }
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+impl ReplayStore for FileStore {
+    fn check_and_insert(
+        &self,
+        key: B256,
+        binding: Binding,
+        now: u64,
+        expires_at: u64,
+    ) -> Result<Option<Binding>, Box<dyn Error + Send + Sync>> {
+        let mut file = self.file.lock().map_err(|_| "replay cache lock is poisoned")?;
+        let existing = self.memory.check_and_insert(key, binding, now, expires_at)?;
+        if existing.is_none() {
+            let line = serde_json::to_string(&Entry { key, binding, expires_at })?;
+            writeln!(file, "{line}")?;
+            file.flush()?;
+        }
+        Ok(existing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("replay-{name}-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn entries_survive_a_restart() {
+        let path = path("restart");
+        let (key, first, second) = (B256::with_last_byte(1), B256::with_last_byte(2), B256::with_last_byte(3));
+        let store = FileStore::open(&path, 100).unwrap();
+        assert_eq!(store.check_and_insert(key, first, 100, 200).unwrap(), None);
+        drop(store);
+
+        let store = FileStore::open(&path, 150).unwrap();
+        assert_eq!(store.check_and_insert(key, second, 150, 250).unwrap(), Some(first));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn compacts_expired_and_malformed_entries_on_open() {
+        let path = path("compact");
+        let (live, expired) = (B256::with_last_byte(1), B256::with_last_byte(2));
+        let store = FileStore::open(&path, 100).unwrap();
+        store.check_and_insert(live, B256::ZERO, 100, 300).unwrap();
+        store.check_and_insert(expired, B256::ZERO, 100, 200).unwrap();
+        drop(store);
+        // A line cut short by a crash
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"key\":\"0x").unwrap();
+
+        let store = FileStore::open(&path, 250).unwrap();
+        let lines = fs::read_to_string(&path).unwrap();
+        assert_eq!(lines.lines().count(), 1);
+        assert!(lines.contains(&live.to_string()));
+        assert_eq!(store.check_and_insert(expired, B256::with_last_byte(9), 250, 350).unwrap(), None);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use std::error::Error;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use alloy::primitives::{keccak256, Address, B256, U256};
+use tracing::{info, instrument, warn};
+use super::file_store::FileStore;
+use super::memory_store::MemoryStore;
+use super::store::{Binding, ReplayStore};
+
+const DEFAULT_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayMode {
+    Reject,
+    Flag,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayStatus {
+    // First time this proof is seen, or the same request is retried
+    Fresh,
+    // Proof was already used for another request, but REPLAY_MODE=flag
+    Flagged,
+    Replayed,
+}
+
+pub struct ReplayCache {
+    store: Box<dyn ReplayStore>,
+    ttl: u64,
+    mode: ReplayMode,
+}
+
+static REPLAY_CACHE: OnceLock<ReplayCache> = OnceLock::new();
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time before unix epoch")
+        .as_secs()
+}
+
+fn binding(
+    registry: &Address,
+    chain_id: u64,
+    credential_group_id: &U256,
+    app_id: &U256,
+    semaphore_identity_commitment: &U256,
+) -> Binding {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(registry.as_slice());
+    buf.extend_from_slice(&chain_id.to_be_bytes());
+    buf.extend_from_slice(&credential_group_id.to_be_bytes::<32>());
+    buf.extend_from_slice(&app_id.to_be_bytes::<32>());
+    buf.extend_from_slice(&semaphore_identity_commitment.to_be_bytes::<32>());
+    keccak256(&buf)
+}
+
+impl ReplayCache {
+    #[instrument(
+        name="replay_cache_loader",
+        level="info",
+        err
+    )]
+    pub fn init() -> Result<(), Box<dyn Error>> {
+        let mode = match std::env::var("REPLAY_MODE").as_deref() {
+            Ok("reject") | Err(_) => ReplayMode::Reject,
+            Ok("flag") => ReplayMode::Flag,
+            Ok("off") => ReplayMode::Off,
+            Ok(mode) => return Err(format!("unsupported REPLAY_MODE {mode}").into()),
+        };
+        let ttl = match std::env::var("REPLAY_TTL_SECS") {
+            Ok(ttl) => ttl.parse::<u64>().map_err(|e| format!("invalid REPLAY_TTL_SECS: {e}"))?,
+            Err(_) => DEFAULT_TTL_SECS,
+        };
+        let store: Box<dyn ReplayStore> = match std::env::var("REPLAY_STORE_PATH") {
+            Ok(path) => Box::new(FileStore::open(&path, now())?),
+            Err(_) => Box::new(MemoryStore::default()),
+        };
+        info!("replay protection: {mode:?}, ttl {ttl}s");
+
+        REPLAY_CACHE
+            .set(ReplayCache { store, ttl, mode })
+            .map_err(|_| "Already initialized")?;
+        Ok(())
+    }
+
+    // `key` identifies the proof (attestation or OAuth message hash), the
+    // rest is what it is being used for. Re-submitting the same request is
+    // allowed, using the proof for anything else is a replay. Called once
+    // the request has passed every check, as it records the proof.
+    pub fn check(
+        key: B256,
+        registry: &Address,
+        chain_id: u64,
+        credential_group_id: &U256,
+        app_id: &U256,
+        semaphore_identity_commitment: &U256,
+    ) -> Result<ReplayStatus, Box<dyn Error + Send + Sync>> {
+        let cache = REPLAY_CACHE.get().ok_or("replay cache is not initialized")?;
+        if cache.mode == ReplayMode::Off {
+            return Ok(ReplayStatus::Fresh);
+        }
+
+        let binding = binding(registry, chain_id, credential_group_id, app_id, semaphore_identity_commitment);
+
+        let now = now();
+        match cache.store.check_and_insert(key, binding, now, now + cache.ttl)? {
+            Some(existing) if existing != binding => {
+                warn!("proof {key} was already used for another request");
+                match cache.mode {
+                    ReplayMode::Flag => Ok(ReplayStatus::Flagged),
+                    _ => Ok(ReplayStatus::Replayed),
+                }
+            },
+            _ => Ok(ReplayStatus::Fresh),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_covers_the_attestation_target() {
+        let registry = Address::with_last_byte(1);
+        let (group, app, commitment) = (U256::from(1), U256::from(2), U256::from(3));
+        let original = binding(&registry, 8453, &group, &app, &commitment);
+        assert_ne!(original, binding(&Address::with_last_byte(2), 8453, &group, &app, &commitment));
+        assert_ne!(original, binding(&registry, 84532, &group, &app, &commitment));
+        assert_ne!(original, binding(&registry, 8453, &group, &U256::from(4), &commitment));
+        assert_eq!(original, binding(&registry, 8453, &U256::from_str_radix("01", 10).unwrap(), &app, &commitment));
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+use alloy::primitives::B256;
+use super::store::{Binding, ReplayStore};
+
+const PRUNE_INTERVAL_SECS: u64 = 60;
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Default)]
+struct Entries {
+    entries: HashMap<B256, (Binding, u64)>,
+    next_prune: u64,
+    prune_at_len: usize,
+}
+
+impl Entries {
+    // Expired entries are dropped once a minute, or sooner when the map
+    // has doubled since the last pruning, instead of on every check
+    fn prune(&mut self, now: u64) {
+        if now < self.next_prune && self.entries.len() < self.prune_at_len {
+            return;
+        }
+        self.entries.retain(|_, (_, expiry)| *expiry > now);
+        self.next_prune = now + PRUNE_INTERVAL_SECS;
+        self.prune_at_len = (self.entries.len() * 2).max(PRUNE_THRESHOLD);
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: Mutex<Entries>,
+}
+
+impl ReplayStore for MemoryStore {
+    fn check_and_insert(
+        &self,
+        key: B256,
+        binding: Binding,
+        now: u64,
+        expires_at: u64,
+    ) -> Result<Option<Binding>, Box<dyn Error + Send + Sync>> {
+        let mut entries = self.entries.lock().map_err(|_| "replay cache lock is poisoned")?;
+        entries.prune(now);
+        if let Some((existing, expiry)) = entries.entries.get(&key)
+            && *expiry > now
+        {
+            return Ok(Some(*existing));
+        }
+        entries.entries.insert(key, (binding, expires_at));
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_the_first_binding() {
+        let store = MemoryStore::default();
+        let (key, first, second) = (B256::with_last_byte(1), B256::with_last_byte(2), B256::with_last_byte(3));
+        assert_eq!(store.check_and_insert(key, first, 100, 200).unwrap(), None);
+        assert_eq!(store.check_and_insert(key, first, 150, 250).unwrap(), Some(first));
+        // The manager reports this one as a replay
+        assert_eq!(store.check_and_insert(key, second, 150, 250).unwrap(), Some(first));
+    }
+
+    #[test]
+    fn forgets_expired_entries() {
+        let store = MemoryStore::default();
+        let (key, first, second) = (B256::with_last_byte(1), B256::with_last_byte(2), B256::with_last_byte(3));
+        store.check_and_insert(key, first, 100, 200).unwrap();
+        // Expired, even though the map was not pruned yet
+        assert_eq!(store.check_and_insert(key, second, 200, 300).unwrap(), None);
+        assert_eq!(store.check_and_insert(key, first, 250, 350).unwrap(), Some(second));
+    }
+
+    #[test]
+    fn prunes_once_the_interval_has_passed() {
+        let store = MemoryStore::default();
+        store.check_and_insert(B256::with_last_byte(1), B256::ZERO, 100, 110).unwrap();
+        store.check_and_insert(B256::with_last_byte(2), B256::ZERO, 120, 1000).unwrap();
+        assert_eq!(store.entries.lock().unwrap().entries.len(), 2);
+        store.check_and_insert(B256::with_last_byte(3), B256::ZERO, 100 + PRUNE_INTERVAL_SECS, 1000).unwrap();
+        assert_eq!(store.entries.lock().unwrap().entries.len(), 2);
+    }
+}
@@ -0,0 +1,18 @@
+use std::error::Error;
+use alloy::primitives::B256;
+
+// What the proof was used for the first time it was seen: the hash of the
+// registry, chain, credential group, app and Semaphore commitment of that request
+pub type Binding = B256;
+
+pub trait ReplayStore: Send + Sync {
+    // Returns the binding already recorded for `key`, or records
+    // `binding` until `expires_at` (unix seconds) and returns None
+    fn check_and_insert(
+        &self,
+        key: B256,
+        binding: Binding,
+        now: u64,
+        expires_at: u64,
+    ) -> Result<Option<Binding>, Box<dyn Error + Send + Sync>>;
+}
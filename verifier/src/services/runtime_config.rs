@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, OnceLock, RwLock};
+use alloy::primitives::Address;
+use crate::core::{OAuthVerification, Verification};
+use crate::custom_handlers;
+use crate::helpers::{
+    load_oauth_signers, load_registry_whitelist, oauth_signers_path,
+    FamilySources, OAuthSigner, OAuthSigners, OAUTH_SOURCE, TLSN_SOURCE,
+};
+use super::{ConfigReloader, Handlers, OAuthVerificationManager, VerificationManager, VERIFICATIONS_PATH};
+
+// Everything `/admin/reload` can change, swapped as a whole. A request
+// takes it once with `current` and sees the same version until it ends,
+// so it never mixes new verifications with old handlers or signers.
+pub struct RuntimeConfig {
+    pub handlers: Handlers,
+    pub verifications: HashMap<String, Arc<Verification>>,
+    pub oauth_verifications: HashMap<String, Arc<OAuthVerification>>,
+    pub oauth_signers: OAuthSigners,
+    pub registries: Vec<Address>,
+}
+
+static RUNTIME_CONFIG: OnceLock<RwLock<Arc<RuntimeConfig>>> = OnceLock::new();
+
+impl RuntimeConfig {
+    // Parses and validates every file without touching the active config.
+    // The registry whitelist comes from the environment, which only
+    // changes on restart, so it is passed in rather than re-read.
+    pub fn load(registries: Vec<Address>) -> Result<Self, Box<dyn Error>> {
+        let handlers = custom_handlers::load_all()?;
+        let verifications = VerificationManager::load_with(VERIFICATIONS_PATH, &handlers)?;
+        let oauth_verifications = OAuthVerificationManager::load(ConfigReloader::oauth_verifications_path())?;
+        let oauth_signers = load_oauth_signers(oauth_signers_path())?;
+
+        let mut families = FamilySources::default();
+        for (id, verification) in &verifications {
+            families.add(id, verification.family, TLSN_SOURCE, &verification.host)?;
+        }
+        for (id, verification) in &oauth_verifications {
+            families.add(id, verification.family, OAUTH_SOURCE, &verification.domain)?;
+        }
+
+        Ok(Self {
+            handlers,
+            verifications,
+            oauth_verifications,
+            oauth_signers,
+            registries,
+        })
+    }
+
+    pub fn init() -> Result<(), Box<dyn Error>> {
+        Self::replace(Self::load(load_registry_whitelist()?)?);
+        Ok(())
+    }
+
+    pub fn current() -> Arc<RuntimeConfig> {
+        let lock = RUNTIME_CONFIG.get().expect("runtime config is not loaded");
+        match lock.read() {
+            Ok(active) => active.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    // Requests holding the previous version keep using it
+    pub fn replace(config: RuntimeConfig) {
+        let config = Arc::new(config);
+        let lock = RUNTIME_CONFIG.get_or_init(|| RwLock::new(config.clone()));
+        match lock.write() {
+            Ok(mut active) => *active = config,
+            Err(poisoned) => *poisoned.into_inner() = config,
+        }
+    }
+
+    pub fn oauth_signers(&self, credential_group_id: &str) -> Option<&[OAuthSigner]> {
+        self.oauth_signers
+            .get(credential_group_id)
+            .map(|signers| &signers[..])
+            .filter(|signers| !signers.is_empty())
+    }
+
+    pub fn is_registry_whitelisted(&self, address: &Address) -> bool {
+        self.registries.contains(address)
+    }
+}
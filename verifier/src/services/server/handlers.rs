@@ -1,3 +1,4 @@
 pub mod root;
 pub mod verify_tlsn;
-pub mod verify_oauth;
\ No newline at end of file
+pub mod verify_oauth;
+pub mod admin_reload;
\ No newline at end of file
@@ -0,0 +1,53 @@
+use axum::http::{header, HeaderMap};
+use axum::Json;
+use serde::Serialize;
+use subtle::ConstantTimeEq;
+use tracing::{error, info, instrument};
+use crate::helpers::{ApiError, ErrorCode};
+use crate::services::{ConfigReloader, ReloadSummary};
+
+#[derive(Serialize)]
+pub struct ReloadResponse {
+    success: bool,
+    #[serde(flatten)]
+    summary: ReloadSummary,
+}
+
+// The admin API is disabled unless ADMIN_TOKEN is set
+fn authorize(headers: &HeaderMap) -> Result<(), ApiError> {
+    let Ok(token) = std::env::var("ADMIN_TOKEN") else {
+        return Err(ApiError::unauthorized(ErrorCode::AdminUnauthorized, "Admin API is disabled"));
+    };
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    // Constant time, so that the token can't be guessed byte by byte
+    let matches = provided.is_some_and(|provided| bool::from(provided.as_bytes().ct_eq(token.as_bytes())));
+    if token.is_empty() || !matches {
+        return Err(ApiError::unauthorized(ErrorCode::AdminUnauthorized, "Wrong admin token"));
+    }
+    Ok(())
+}
+
+#[instrument(name="handler", skip(headers))]
+pub async fn handle(
+    headers: HeaderMap,
+) -> Result<Json<ReloadResponse>, ApiError> {
+    authorize(&headers)?;
+    info!("config reload requested");
+
+    // File I/O and WASM compilation, kept off the async workers
+    let summary = tokio::task::spawn_blocking(|| ConfigReloader::reload().map_err(|e| e.to_string()))
+        .await
+        .map_err(|e| {
+            error!("config reload panicked: {e}");
+            ApiError::internal(ErrorCode::ConfigReloadFailed, e)
+        })?
+        .map_err(|e| {
+            error!("config reload failed: {e}");
+            ApiError::bad_request(ErrorCode::ConfigReloadFailed, e)
+        })?;
+
+    Ok(Json(ReloadResponse { success: true, summary }))
+}
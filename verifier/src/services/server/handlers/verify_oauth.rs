@@ -2,13 +2,15 @@ use std::str::FromStr;
 use alloy::signers::Signature;
 use alloy::{sol};
 use alloy::primitives::{aliases::U256, keccak256, B256};
-use alloy::sol_types::SolValue;
+use alloy::sol_types::{Eip712Domain, SolStruct, SolValue};
 use axum::extract::rejection::JsonRejection;
 use axum::Json;
+use chrono::Utc;
 use serde::{Serialize, Deserialize};
 use tracing::{info, error, instrument, trace};
-use crate::helpers::{random_credential_id, credential_id_from_bytes, verifier_response, VerifyResponse, ApiError, ErrorCode, get_oauth_signer};
-use crate::services::{OAuthVerificationManager};
+use crate::helpers::{random_credential_id, Credential, credential_id_from_bytes, credential_tag, OAUTH_SOURCE, attestation_target, verifier_response, VerifyResponse, ApiError, ErrorCode};
+use crate::core::{OAuthTimestampError, VerificationFailed};
+use crate::services::{ReplayCache, ReplayStatus, RuntimeConfig};
 
 sol! {
     #[derive(Deserialize, Serialize, Debug)]
@@ -19,11 +21,103 @@ sol! {
         uint256 score;
         uint256 timestamp;
     }
+
+    #[derive(Deserialize, Serialize, Debug)]
+    struct OauthMessageV2 {
+        string domain;
+        #[serde(rename = "userId")]
+        string user_id;
+        uint256 score;
+        uint256 timestamp;
+        #[serde(rename = "credentialGroupId")]
+        uint256 credential_group_id;
+        #[serde(rename = "appId")]
+        uint256 app_id;
+        // Zero when the message is not bound to a commitment
+        #[serde(rename = "semaphoreIdentityCommitment", default)]
+        uint256 semaphore_identity_commitment;
+    }
+}
+
+// The v2 layout also signs over the credential group and app id (and
+// optionally the commitment) the message is meant for
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum SignedOauthMessage {
+    V2(OauthMessageV2),
+    Legacy(OauthMessage),
+}
+
+impl SignedOauthMessage {
+    fn domain(&self) -> &str {
+        match self {
+            SignedOauthMessage::V2(message) => &message.domain,
+            SignedOauthMessage::Legacy(message) => &message.domain,
+        }
+    }
+
+    fn user_id(&self) -> &str {
+        match self {
+            SignedOauthMessage::V2(message) => &message.user_id,
+            SignedOauthMessage::Legacy(message) => &message.user_id,
+        }
+    }
+
+    fn score(&self) -> U256 {
+        match self {
+            SignedOauthMessage::V2(message) => message.score,
+            SignedOauthMessage::Legacy(message) => message.score,
+        }
+    }
+
+    fn timestamp(&self) -> U256 {
+        match self {
+            SignedOauthMessage::V2(message) => message.timestamp,
+            SignedOauthMessage::Legacy(message) => message.timestamp,
+        }
+    }
+
+    fn hash(&self) -> B256 {
+        match self {
+            SignedOauthMessage::V2(message) => keccak256(message.abi_encode_params()),
+            SignedOauthMessage::Legacy(message) => keccak256(message.abi_encode_params()),
+        }
+    }
+
+    fn eip712_signing_hash(&self, domain: &Eip712Domain) -> B256 {
+        match self {
+            SignedOauthMessage::V2(message) => message.eip712_signing_hash(domain),
+            SignedOauthMessage::Legacy(message) => message.eip712_signing_hash(domain),
+        }
+    }
+
+    fn check_binding(
+        &self,
+        credential_group_id: &U256,
+        app_id: &U256,
+        semaphore_identity_commitment: &U256,
+    ) -> Result<(), &'static str> {
+        let SignedOauthMessage::V2(message) = self else {
+            return Ok(());
+        };
+        if message.credential_group_id != *credential_group_id {
+            return Err("OAuth message is signed for another credential group");
+        }
+        if message.app_id != *app_id {
+            return Err("OAuth message is signed for another app");
+        }
+        if !message.semaphore_identity_commitment.is_zero()
+            && message.semaphore_identity_commitment != *semaphore_identity_commitment
+        {
+            return Err("OAuth message is signed for another Semaphore identity commitment");
+        }
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Debug)]
 pub struct VerifyRequest {
-    message: OauthMessage,
+    message: SignedOauthMessage,
     signature: String,
     semaphore_identity_commitment: String,
     credential_group_id: String,
@@ -43,8 +137,8 @@ pub async fn handle(
     name="handler",
     skip(payload),
     fields(
-        domain = %payload.message.domain,
-        user = %payload.message.user_id
+        domain = %payload.message.domain(),
+        user = %payload.message.user_id()
     )
 )]
 async fn handle_inner(
@@ -53,7 +147,7 @@ async fn handle_inner(
     info!("verification started");
     trace!("{:?}", &payload);
 
-    let message = keccak256(payload.message.abi_encode_params());
+    let message = payload.message.hash();
 
     // Parse signature
     let signature = payload.signature.parse::<Signature>()
@@ -62,13 +156,25 @@ async fn handle_inner(
             ApiError::bad_request(ErrorCode::SignatureParseFailed, e)
         })?;
 
-    // Recover signer address
-    let recovered_address = signature.recover_address_from_msg(message.as_slice())
-        .map_err(|e| {
-            error!("failed to recover address: {}", e);
-            ApiError::bad_request(ErrorCode::AddressRecoveryFailed, e)
+    let runtime = RuntimeConfig::current();
+    let verification = runtime.oauth_verifications
+        .get(&payload.credential_group_id)
+        .ok_or_else(|| {
+            error!("verification is not found");
+            ApiError::internal(ErrorCode::VerificationNotFound, "verification is not found")
         })?;
 
+    // Recover signer address
+    let recovered_address = match verification.signature_scheme.eip712_domain() {
+        Some(domain) => signature.recover_address_from_prehash(
+            &payload.message.eip712_signing_hash(&domain)
+        ),
+        None => signature.recover_address_from_msg(message.as_slice()),
+    }.map_err(|e| {
+        error!("failed to recover address: {}", e);
+        ApiError::bad_request(ErrorCode::AddressRecoveryFailed, e)
+    })?;
+
     let app_id_u256 = U256::from_str(payload.app_id.as_str()).map_err(|e| {
         error!("invalid app_id: {e}");
         ApiError::bad_request(ErrorCode::InvalidAppId, e)
@@ -79,21 +185,57 @@ async fn handle_inner(
     let is_dev = matches!(std::env::var("ENV"), Ok(ref v) if v == "dev");
 
     if !is_dev || std::env::var("STAGING_VALIDATE_OAUTH_SIGNER").is_ok_and(|v| v == "true") {
-        let expected_signer = get_oauth_signer(&payload.credential_group_id)
+        let expected_signers = runtime.oauth_signers(&payload.credential_group_id)
             .ok_or_else(|| {
                 error!("no OAuth signer configured for credential_group_id {}", payload.credential_group_id);
                 ApiError::unauthorized(ErrorCode::WrongOauthSigner, "No OAuth signer configured for this credential group")
             })?;
-        if recovered_address != *expected_signer {
+        // During a key rotation the old and the new signer are both valid
+        let now = Utc::now();
+        let trusted = expected_signers
+            .iter()
+            .any(|signer| signer.address == recovered_address && signer.is_valid_at(now));
+        if !trusted {
+            error!("untrusted OAuth signer {recovered_address}");
             return Err(ApiError::unauthorized(ErrorCode::WrongOauthSigner, "Wrong OAuth signer"));
         }
     }
 
-    let credential_id: B256 = if is_dev && std::env::var("STAGING_USE_RANDOM_ID").is_ok_and(|v| v == "true") {
+    if matches!(payload.message, SignedOauthMessage::Legacy(_)) && !verification.accepts_legacy_message(Utc::now()) {
+        error!("legacy OAuth message is not accepted");
+        return Err(ApiError::bad_request(
+            ErrorCode::LegacyOauthMessageRejected,
+            "Legacy OAuth message layout is not accepted for this credential group",
+        ));
+    }
+
+    let credential_group_id = U256::from_str(payload.credential_group_id.as_str()).map_err(|e| {
+        error!("invalid credential_group_id: {e}");
+        ApiError::bad_request(ErrorCode::InvalidCredentialGroupId, e)
+    })?;
+
+    let semaphore_identity_commitment = U256::from_str(
+        payload.semaphore_identity_commitment.as_str()
+    ).map_err(|e| {
+        error!("invalid Semaphore Identity commitment: {e}");
+        ApiError::bad_request(ErrorCode::InvalidSemaphoreCommitment, e)
+    })?;
+
+    payload.message.check_binding(
+        &credential_group_id,
+        &app_id_u256,
+        &semaphore_identity_commitment,
+    ).map_err(|e| {
+        error!("{e}");
+        ApiError::bad_request(ErrorCode::OauthMessageBindingMismatch, e)
+    })?;
+
+    let credential_ids = if is_dev && std::env::var("STAGING_USE_RANDOM_ID").is_ok_and(|v| v == "true") {
         random_credential_id()
     } else {
         credential_id_from_bytes(
-            payload.message.user_id.as_bytes(),
+            &credential_tag(verification.family, OAUTH_SOURCE, &verification.domain),
+            payload.message.user_id().as_bytes(),
             &app_id_u256,
         ).map_err(|e| {
             error!("credential ID computation failed: {e}");
@@ -101,34 +243,50 @@ async fn handle_inner(
         })?
     };
 
-    let verification = OAuthVerificationManager::get(&payload.credential_group_id)
-        .ok_or_else(|| {
-            error!("verification is not found");
-            ApiError::internal(ErrorCode::VerificationNotFound, "verification is not found")
-        })?
-        .clone();
+    verification.check_timestamp(payload.message.timestamp().saturating_to::<u64>())
+        .map_err(|e| match e {
+            OAuthTimestampError::Expired { .. } => {
+                ApiError::bad_request(ErrorCode::OauthMessageExpired, e)
+            },
+            OAuthTimestampError::FutureDated { .. } => {
+                ApiError::bad_request(ErrorCode::OauthMessageFutureDated, e)
+            },
+        })?;
 
     verification.check(
-        payload.message.domain,
-        payload.message.score.to::<i32>()
-    ).await.map_err(
-        |e| { ApiError::bad_request(ErrorCode::VerificationCheckFailed, e) }
-    )?;
+        payload.message.domain().to_string(),
+        payload.message.score().to::<i32>()
+    ).await.map_err(|e| match e.downcast_ref::<VerificationFailed>() {
+        Some(failed) => ApiError::bad_request(ErrorCode::VerificationCheckFailed, &e)
+            .with_details(failed.failures.clone()),
+        None => ApiError::bad_request(ErrorCode::VerificationCheckFailed, e),
+    })?;
 
-    // Build Verifier message
-    let semaphore_identity_commitment = U256::from_str(
-        payload.semaphore_identity_commitment.as_str()
+    let registry = attestation_target(&runtime, &payload.registry, payload.chain_id)?;
+    let replay = ReplayCache::check(
+        message,
+        &registry,
+        payload.chain_id,
+        &credential_group_id,
+        &app_id_u256,
+        &semaphore_identity_commitment,
     ).map_err(|e| {
-        error!("invalid Semaphore Identity commitment: {e}");
-        ApiError::bad_request(ErrorCode::InvalidSemaphoreCommitment, e)
+        error!("replay check failed: {e}");
+        ApiError::internal(ErrorCode::ReplayCheckFailed, e)
     })?;
+    if replay == ReplayStatus::Replayed {
+        return Err(ApiError::conflict(ErrorCode::ReplayDetected, "OAuth message was already used for another request"));
+    }
 
     verifier_response(
-        payload.registry,
+        registry,
         payload.chain_id,
         payload.credential_group_id,
         payload.app_id,
         semaphore_identity_commitment,
-        credential_id,
+        Credential {
+            family: verification.family,
+            ids: credential_ids,
+        },
     ).await
 }
@@ -4,7 +4,9 @@ use axum::extract::rejection::JsonRejection;
 use axum::Json;
 use serde::Deserialize;
 use crate::tlsn;
-use crate::helpers::{verifier_response, VerifyResponse, ApiError, ErrorCode};
+use crate::core::{PresentationExpired, VerificationFailed};
+use crate::services::{ReplayCache, ReplayStatus, RuntimeConfig};
+use crate::helpers::{attestation_target, verifier_response, VerifyResponse, ApiError, ErrorCode};
 use tracing::{info, error, instrument, warn, trace};
 
 #[derive(Deserialize, Debug)]
@@ -48,10 +50,18 @@ async fn handle_inner(
             ApiError::bad_request(ErrorCode::PresentationDeserializeFailed, e)
         })?;
 
-    let credential_id = tlsn::verify_proof(presentation, &payload.credential_group_id, &payload.app_id).await
+    let runtime = RuntimeConfig::current();
+    let proof = tlsn::verify_proof(&runtime, presentation, &payload.credential_group_id, &payload.app_id).await
         .map_err(|e| {
             warn!("verification failed");
-            ApiError::bad_request(ErrorCode::ProofVerificationFailed, e)
+            if e.is::<PresentationExpired>() {
+                ApiError::bad_request(ErrorCode::PresentationExpired, e)
+            } else if let Some(failed) = e.downcast_ref::<VerificationFailed>() {
+                ApiError::bad_request(ErrorCode::ProofVerificationFailed, &e)
+                    .with_details(failed.failures.clone())
+            } else {
+                ApiError::bad_request(ErrorCode::ProofVerificationFailed, e)
+            }
         })?;
 
     let semaphore_identity_commitment = U256::from_str(
@@ -61,12 +71,36 @@ async fn handle_inner(
         ApiError::bad_request(ErrorCode::InvalidSemaphoreCommitment, e)
     })?;
 
+    let app_id = U256::from_str(payload.app_id.as_str()).map_err(|e| {
+        error!("invalid app_id: {e}");
+        ApiError::bad_request(ErrorCode::InvalidAppId, e)
+    })?;
+    let credential_group_id = U256::from_str(payload.credential_group_id.as_str()).map_err(|e| {
+        error!("invalid credential_group_id: {e}");
+        ApiError::bad_request(ErrorCode::InvalidCredentialGroupId, e)
+    })?;
+    let registry = attestation_target(&runtime, &payload.registry, payload.chain_id)?;
+    let replay = ReplayCache::check(
+        proof.attestation_hash,
+        &registry,
+        payload.chain_id,
+        &credential_group_id,
+        &app_id,
+        &semaphore_identity_commitment,
+    ).map_err(|e| {
+        error!("replay check failed: {e}");
+        ApiError::internal(ErrorCode::ReplayCheckFailed, e)
+    })?;
+    if replay == ReplayStatus::Replayed {
+        return Err(ApiError::conflict(ErrorCode::ReplayDetected, "presentation was already used for another request"));
+    }
+
     verifier_response(
-        payload.registry,
+        registry,
         payload.chain_id,
         payload.credential_group_id,
         payload.app_id,
         semaphore_identity_commitment,
-        credential_id,
+        proof.credential,
     ).await
 }
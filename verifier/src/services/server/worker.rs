@@ -1,13 +1,14 @@
 use axum::{routing::get, routing::post, Router};
 use std::error::Error;
-use super::handlers::{root, verify_tlsn, verify_oauth};
+use super::handlers::{root, verify_tlsn, verify_oauth, admin_reload};
 use crate::config;
 
 pub async fn run() -> Result<(), Box<dyn Error>> {
     let app = Router::new()
         .route("/", get(root::handle))
         .route("/verify", post(verify_tlsn::handle))
-        .route("/verify/oauth", post(verify_oauth::handle));
+        .route("/verify/oauth", post(verify_oauth::handle))
+        .route("/admin/reload", post(admin_reload::handle));
     let port = &config::get().port;
 
     let addr = format!("0.0.0.0:{port}");
@@ -1,64 +1,49 @@
 use std::fs::File;
 use std::io::BufReader;
-use std::sync::{Arc, OnceLock, RwLock};
+use std::sync::Arc;
 use crate::core::Verification;
 use std::collections::HashMap;
 use tracing::{debug, error, instrument};
-use crate::services::handlers_manager::HandlersManager;
-
-static VERIFICATIONS: OnceLock<RwLock<HashMap<String, Arc<Verification>>>> = OnceLock::new();
+use crate::services::handlers_manager::Handlers;
 
 pub struct VerificationManager;
 
 impl VerificationManager {
-    pub fn get(id: &str) -> Option<Arc<Verification>> {
-        VERIFICATIONS
-            .get()
-            .and_then(|map| map.read().ok()?.get(id).cloned())
-    }
-
+    // Parses a config and validates its custom handlers against `handlers`
     #[instrument(
         name="verification_manager_loader",
         level="info",
+        skip(handlers),
         err
     )]
-    pub async fn from_file(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn load_with(path: &str, handlers: &Handlers) -> Result<HashMap<String, Arc<Verification>>, Box<dyn std::error::Error>> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
 
         let raw_verifications: HashMap<String, Verification> = serde_json::from_reader(reader)?;
-        let arc_verifications = raw_verifications
-            .into_iter()
-            .map(|(k, v)| (k, Arc::new(v)))
-            .collect();
-
-        VERIFICATIONS
-            .set(RwLock::new(arc_verifications))
-            .map_err(|_| {
-                error!("already initialized");
-                "Already initialized"
-            })?;
-
-        let handlers = HandlersManager::get_handlers().read().await;
 
         // Validating verifications
-        for (_, verification) in VERIFICATIONS.get().unwrap().read().unwrap().iter() {
-            if let Some(user_id_handler) = &verification.user_id.custom_handler {
-                if handlers.get(user_id_handler).is_none() {
-                    error!("handler {user_id_handler} is not found");
-                    return Err(format!("handler {} is not found", user_id_handler).into());
-                }
-            }
-            for check in verification.checks.iter() {
-                if let Some(handler) = &check.custom_handler {
-                    if handlers.get(handler).is_none() {
-                        error!("handler {handler} is not found");
-                        return Err(format!("handler {} is not found", handler).into());
-                    }
+        for verification in raw_verifications.values() {
+            let checks = verification.checks.iter().flat_map(|check| check.leaves());
+            for check in std::iter::once(&verification.user_id).chain(checks) {
+                let Some(name) = &check.custom_handler else {
+                    continue;
+                };
+                let Some(handler) = handlers.get(name) else {
+                    error!("handler {name} is not found");
+                    return Err(format!("handler {} is not found", name).into());
+                };
+                if let Err(e) = handler.validate_params(&check.handler_params) {
+                    error!("handler {name} of group {}: {e}", verification.id);
+                    return Err(format!("handler {name} of group {}: {e}", verification.id).into());
                 }
             }
             debug!("loaded {:?}", verification);
         }
-        Ok(())
+
+        Ok(raw_verifications
+            .into_iter()
+            .map(|(k, v)| (k, Arc::new(v)))
+            .collect())
     }
 }
@@ -1,11 +1,47 @@
+mod backend;
+mod local;
+mod remote;
+pub mod mock_server;
+
+use std::error::Error;
 use std::sync::OnceLock;
-use crate::{config};
-use alloy::signers::{local::PrivateKeySigner};
+use alloy::primitives::{eip191_hash_message, Signature};
+use tracing::{info, instrument};
+use crate::config;
+pub use backend::SigningBackend;
+use local::LocalSigner;
+use remote::RemoteSigner;
+
+static SIGNER: OnceLock<Box<dyn SigningBackend>> = OnceLock::new();
+
+// SIGNER_BACKEND picks where the attestation key lives: `env` (default)
+// reads PRIVATE_KEY_HEX, `keystore` decrypts a JSON keystore and `remote`
+// asks a signing service that never hands out the key
+#[instrument(
+    name="signer_loader",
+    level="info",
+    err
+)]
+pub fn init() -> Result<(), Box<dyn Error>> {
+    let signer: Box<dyn SigningBackend> = match std::env::var("SIGNER_BACKEND").as_deref() {
+        Ok("env") | Err(_) => Box::new(LocalSigner::from_key(config::private_key())),
+        Ok("keystore") => Box::new(LocalSigner::from_keystore()?),
+        Ok("remote") => Box::new(RemoteSigner::from_env()?),
+        Ok(backend) => return Err(format!("unsupported SIGNER_BACKEND {backend}").into()),
+    };
+    info!("attestation signer: {} ({})", signer.address(), signer.kind());
+
+    SIGNER
+        .set(signer)
+        .map_err(|_| "Already initialized")?;
+    Ok(())
+}
 
-static SIGNER: OnceLock<PrivateKeySigner> = OnceLock::new();
+pub fn get() -> &'static dyn SigningBackend {
+    SIGNER.get().expect("signer is not initialized").as_ref()
+}
 
-pub fn get() -> &'static PrivateKeySigner {
-    SIGNER.get_or_init(||
-        PrivateKeySigner::from(config::get().private_key.clone())
-    )
-}
\ No newline at end of file
+// EIP-191 personal message signature, as PrivateKeySigner::sign_message does
+pub async fn sign_message(message: &[u8]) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+    get().sign_hash(eip191_hash_message(message)).await
+}
@@ -0,0 +1,16 @@
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use alloy::primitives::{Address, Signature, B256};
+
+pub type SignFuture<'a> = Pin<Box<dyn Future<Output = Result<Signature, Box<dyn Error + Send + Sync>>> + Send + 'a>>;
+
+pub trait SigningBackend: Send + Sync {
+    fn kind(&self) -> &'static str;
+
+    // Known up front, so `/` can report it without a round trip
+    fn address(&self) -> Address;
+
+    // Signs a 32 byte prehash, the signature must recover to `address()`
+    fn sign_hash(&self, hash: B256) -> SignFuture<'_>;
+}
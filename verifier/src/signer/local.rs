@@ -0,0 +1,53 @@
+use std::error::Error;
+use alloy::primitives::{Address, B256};
+use alloy::signers::k256::SecretKey;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::Signer;
+use super::backend::{SignFuture, SigningBackend};
+
+pub struct LocalSigner {
+    signer: PrivateKeySigner,
+    kind: &'static str,
+}
+
+impl LocalSigner {
+    pub fn from_key(key: SecretKey) -> Self {
+        Self {
+            signer: PrivateKeySigner::from(key),
+            kind: "env",
+        }
+    }
+
+    // Password comes either inline or from a file, e.g. a mounted secret
+    pub fn from_keystore() -> Result<Self, Box<dyn Error>> {
+        let path = std::env::var("SIGNER_KEYSTORE_PATH")
+            .map_err(|_| "SIGNER_KEYSTORE_PATH is not set")?;
+        let password = match std::env::var("SIGNER_KEYSTORE_PASSWORD_FILE") {
+            Ok(file) => std::fs::read_to_string(&file)
+                .map_err(|e| format!("failed to read {file}: {e}"))?
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+            Err(_) => std::env::var("SIGNER_KEYSTORE_PASSWORD")
+                .map_err(|_| "SIGNER_KEYSTORE_PASSWORD or SIGNER_KEYSTORE_PASSWORD_FILE is not set")?,
+        };
+        let signer = PrivateKeySigner::decrypt_keystore(&path, password)
+            .map_err(|e| format!("failed to decrypt keystore {path}: {e}"))?;
+        Ok(Self { signer, kind: "keystore" })
+    }
+}
+
+impl SigningBackend for LocalSigner {
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    fn sign_hash(&self, hash: B256) -> SignFuture<'_> {
+        Box::pin(async move {
+            Ok(self.signer.sign_hash(&hash).await?)
+        })
+    }
+}
@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::sync::Arc;
+use alloy::hex;
+use alloy::signers::k256::SecretKey;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::Signer;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use super::remote::{SignRequest, SignResponse};
+
+struct MockSigner {
+    signer: PrivateKeySigner,
+    token: Option<String>,
+}
+
+async fn address(State(state): State<Arc<MockSigner>>) -> String {
+    state.signer.address().to_string()
+}
+
+async fn sign(
+    State(state): State<Arc<MockSigner>>,
+    headers: HeaderMap,
+    Json(request): Json<SignRequest>,
+) -> Result<Json<SignResponse>, (StatusCode, String)> {
+    if let Some(token) = &state.token {
+        let provided = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if provided != Some(token.as_str()) {
+            return Err((StatusCode::UNAUTHORIZED, "wrong token".to_string()));
+        }
+    }
+    if request.address != state.signer.address() {
+        return Err((StatusCode::NOT_FOUND, format!("unknown key {}", request.address)));
+    }
+    let signature = state.signer
+        .sign_hash(&request.hash)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(SignResponse { signature: signature.to_string() }))
+}
+
+// `verifier mock-signer`: a local stand-in for a KMS speaking the remote
+// signer protocol, for development and tests only. The key is kept in
+// memory; without MOCK_SIGNER_KEY_HEX a new one is generated every start.
+pub async fn run() -> Result<(), Box<dyn Error>> {
+    let signer = match std::env::var("MOCK_SIGNER_KEY_HEX") {
+        Ok(key) => PrivateKeySigner::from(
+            SecretKey::from_slice(&hex::decode(key).map_err(|e| format!("invalid MOCK_SIGNER_KEY_HEX: {e}"))?)
+                .map_err(|e| format!("invalid MOCK_SIGNER_KEY_HEX: {e}"))?
+        ),
+        Err(_) => PrivateKeySigner::random(),
+    };
+    let token = std::env::var("MOCK_SIGNER_TOKEN").ok().filter(|token| !token.is_empty());
+    let port = std::env::var("MOCK_SIGNER_PORT").unwrap_or_else(|_| "3001".to_string());
+    println!("mock signer {} listening on 127.0.0.1:{port}", signer.address());
+
+    let app = Router::new()
+        .route("/", get(address))
+        .route("/sign", post(sign))
+        .with_state(Arc::new(MockSigner { signer, token }));
+    let listener = tokio::net::TcpListener::bind(format!("127.0.0.1:{port}")).await?;
+    axum::serve(listener, app).await?;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::error::Error;
+use std::str::FromStr;
+use std::time::Duration;
+use alloy::primitives::{Address, Signature, B256};
+use alloy::transports::http::reqwest::{header, Client};
+use serde::{Deserialize, Serialize};
+use super::backend::{SignFuture, SigningBackend};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize)]
+pub struct SignRequest {
+    pub address: Address,
+    pub hash: B256,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SignResponse {
+    pub signature: String,
+}
+
+// `POST {REMOTE_SIGNER_URL}/sign` with the prehash to sign. The address is
+// pinned in config rather than asked for, so a misconfigured or compromised
+// signer can't make the verifier sign with an unexpected key.
+pub struct RemoteSigner {
+    client: Client,
+    url: String,
+    token: Option<String>,
+    address: Address,
+}
+
+impl RemoteSigner {
+    pub fn from_env() -> Result<Self, Box<dyn Error>> {
+        let url = std::env::var("REMOTE_SIGNER_URL")
+            .map_err(|_| "REMOTE_SIGNER_URL is not set")?;
+        let address = std::env::var("REMOTE_SIGNER_ADDRESS")
+            .map_err(|_| "REMOTE_SIGNER_ADDRESS is not set")?;
+        let address = Address::from_str(&address)
+            .map_err(|e| format!("invalid REMOTE_SIGNER_ADDRESS: {e}"))?;
+        let client = Client::builder().timeout(TIMEOUT).build()?;
+        Ok(Self {
+            client,
+            url: format!("{}/sign", url.trim_end_matches('/')),
+            token: std::env::var("REMOTE_SIGNER_TOKEN").ok().filter(|token| !token.is_empty()),
+            address,
+        })
+    }
+
+    async fn request(&self, hash: B256) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+        let body = serde_json::to_vec(&SignRequest { address: self.address, hash })?;
+        let mut request = self.client
+            .post(&self.url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("remote signer returned {status}").into());
+        }
+        let response: SignResponse = serde_json::from_slice(&response.bytes().await?)?;
+        let signature = Signature::from_str(&response.signature)
+            .map_err(|e| format!("remote signer returned an invalid signature: {e}"))?;
+
+        let recovered = signature.recover_address_from_prehash(&hash)?;
+        if recovered != self.address {
+            return Err(format!("remote signer signed with {recovered}, expected {}", self.address).into());
+        }
+        Ok(signature)
+    }
+}
+
+impl SigningBackend for RemoteSigner {
+    fn kind(&self) -> &'static str {
+        "remote"
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_hash(&self, hash: B256) -> SignFuture<'_> {
+        Box::pin(self.request(hash))
+    }
+}
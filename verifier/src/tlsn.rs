@@ -1,28 +1,37 @@
 use std::error::Error;
 use std::str::FromStr;
 use alloy::hex::ToHexExt;
-use alloy::primitives::{B256, U256};
+use alloy::primitives::{keccak256, B256, U256};
 use tlsn_core::presentation::{Presentation, PresentationOutput};
 use tlsn_core::{CryptoProvider};
 use tracing::{debug, trace, instrument, error};
 use crate::{config};
-use crate::services::VerificationManager;
-use crate::helpers::credential_id;
+use crate::services::RuntimeConfig;
+use crate::helpers::{credential_id, Credential};
+use crate::core::{CheckContext, Transcript};
+
+pub struct VerifiedProof {
+    pub credential: Credential,
+    // Identifies the attestation, whatever subset of it is presented
+    pub attestation_hash: B256,
+}
 
 #[instrument(
     name="proof_verifier",
     level="info",
-    skip(presentation, credential_group_id),
+    skip(runtime, presentation, credential_group_id),
 )]
 pub async fn verify_proof(
+    runtime: &RuntimeConfig,
     presentation: Presentation,
     credential_group_id: &String,
     app_id: &str,
-) -> Result<B256, Box<dyn Error>> {
+) -> Result<VerifiedProof, Box<dyn Error>> {
     debug!("verification started");
     let PresentationOutput {
         attestation,
         server_name,
+        connection_info,
         transcript,
         ..
     } = presentation.verify(&CryptoProvider::default())?;
@@ -30,16 +39,6 @@ pub async fn verify_proof(
     let server_name = server_name.ok_or("Server name is not set")?;
     let transcript = transcript.ok_or("Transcript is not provided")?;
 
-    let transcript_authed: Vec<String> = transcript.received_authed()
-        .iter_ranges()
-        .filter_map(|range| {
-            let data = &transcript.received_unsafe()[range];
-            std::str::from_utf8(data)
-                .ok()
-                .map(|s| s.to_string())
-        })
-        .collect();
-
     transcript.received_authed().iter_ranges()
         .for_each(|x| {
             trace!("\nrange\n{:?}", x);
@@ -52,34 +51,63 @@ pub async fn verify_proof(
             }
         });
 
-    let verification = VerificationManager::get(&credential_group_id)
+    let verification = runtime.verifications
+        .get(credential_group_id)
         .ok_or_else(|| {
             error!("verification is not found");
             "verification is not found"
-        })?
-        .clone();
+        })?;
 
     let app_id_u256 = U256::from_str(app_id)
         .map_err(|e| -> Box<dyn Error> { format!("invalid app_id: {e}").into() })?;
 
-    let cid = credential_id(&transcript_authed, &verification.user_id, &app_id_u256).await?;
-
-    if attestation.body.verifying_key() != &config::get().notary_key {
-        error!("invalid notary key");
-        return Err(
-            format!("invalid notary key.\nexpected: {}\ngot: {}",
-                    &config::get().notary_key.data.encode_hex_with_prefix(),
-                    attestation.body.verifying_key().data.encode_hex_with_prefix()
-            ).into()
-        );
+    let notary_key = attestation.body.verifying_key();
+    let notary = config::get()
+        .trusted_notary(notary_key, connection_info.time)
+        .ok_or_else(|| {
+            error!("invalid notary key");
+            format!("invalid notary key: {}", notary_key.data.encode_hex_with_prefix())
+        })?;
+    if !verification.accepts_notary(&notary.id) {
+        error!("notary is not accepted for this verification");
+        return Err(format!("notary {} is not accepted for this credential group", notary.id).into());
     }
 
-    verification.check(
-        server_name.to_string(),
-        &transcript_authed,
-        &app_id_u256,
-    ).await?;
+    verification.check_freshness(connection_info.time)?;
+
+    // Parsed only now: decoding the body of a presentation
+    // from an untrusted notary is work nobody vouched for
+    let sent_authed = Transcript::new(
+        transcript.sent_unsafe(),
+        transcript.sent_authed().iter_ranges(),
+    );
+    let transcript_authed = Transcript::new(
+        transcript.received_unsafe(),
+        transcript.received_authed().iter_ranges(),
+    );
+
+    let server_name = server_name.to_string();
+    let ctx = CheckContext {
+        server_name: &server_name,
+        credential_group_id,
+        family: verification.family,
+        app_id: &app_id_u256,
+        sent: &sent_authed,
+        received: &transcript_authed,
+        handlers: &runtime.handlers,
+    };
+    verification.check(&ctx).await?;
+
+    let cid = credential_id(&ctx, &verification.user_id).await?;
+
+    let attestation_hash = keccak256(bincode::serialize(&attestation.header)?);
 
     debug!("proof verified");
-    Ok(cid)
+    Ok(VerifiedProof {
+        credential: Credential {
+            family: verification.family,
+            ids: cid,
+        },
+        attestation_hash,
+    })
 }